@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use error::{Error, Result, column, parse_token};
+use input::read_file;
+use report::Record;
+
+/// File containing the expected answers when none is given.
+pub const DEFAULT_FILE: &str = "answers.toml";
+
+/// Expected answers to the problems, keyed by day and part.
+///
+/// They are stored in a subset of TOML, with a table per day and a string
+/// per part:
+///
+/// ```toml
+/// [01]
+/// part1 = "1069"
+/// part2 = "1268"
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Expected {
+    answers: HashMap<(u32, u32), String>,
+}
+
+impl Expected {
+    /// Parse the content of an answers file.
+    pub fn parse(content: &str) -> Result<Expected> {
+        let mut answers = HashMap::new();
+        let mut day: Option<u32> = None;
+        for (i, line) in content.lines().enumerate() {
+            parse_line(line, &mut day, &mut answers).map_err(|err| err.at_line(i + 1))?;
+        }
+        Ok(Expected { answers })
+    }
+
+    /// Read and parse the answers file at the given path.
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Expected> {
+        let mut content = String::new();
+        read_file(path.as_ref().to_path_buf(), &mut content)?;
+        Expected::parse(&content)
+    }
+
+    /// Expected answer of the part of the problem of the given day.
+    pub fn get(&self, day: u32, part: u32) -> Option<&str> {
+        self.answers.get(&(day, part)).map(|answer| answer.as_str())
+    }
+}
+
+/// Parse a line of an answers file, updating the current day on table headers.
+fn parse_line(line: &str,
+              day: &mut Option<u32>,
+              answers: &mut HashMap<(u32, u32), String>)
+              -> Result<()> {
+    let content = line.split('#').next().unwrap().trim();
+    if content.is_empty() {
+        return Ok(());
+    }
+    if content.starts_with('[') && content.ends_with(']') {
+        *day = Some(parse_token(line, content[1..content.len() - 1].trim())?);
+        return Ok(());
+    }
+    let mut parts = content.splitn(2, '=');
+    let key = parts.next().unwrap().trim();
+    let value = match parts.next() {
+        Some(value) => value.trim(),
+        None => return Err(Error::parse(column(line, content), "Expected `[<day>]` or `part<n> = \"<answer>\"`")),
+    };
+    let part = match key {
+        "part1" => 1,
+        "part2" => 2,
+        _ => return Err(Error::parse(column(line, key), format!("Unknown key `{}`", key))),
+    };
+    if value.len() < 2 || !value.starts_with('"') || !value.ends_with('"') {
+        return Err(Error::parse(column(line, value), "Expected a quoted answer"));
+    }
+    match *day {
+        Some(day) => {
+            answers.insert((day, part), String::from(&value[1..value.len() - 1]));
+            Ok(())
+        },
+        None => Err(Error::parse(column(line, key), "Answer given before any `[<day>]` table")),
+    }
+}
+
+/// Outcome of the verification of a part of a problem.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Status {
+    /// The answer is the expected one.
+    Match,
+    /// The answer differs from the expected one, or no answer was found.
+    Mismatch { expected: String, found: Option<String> },
+    /// The answer is not in the answers file.
+    Missing { found: String },
+    /// The problem failed to be solved.
+    Failed(String),
+}
+
+/// Verification of a part of a problem, `part` being `None` for a failure of
+/// the whole problem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub day: u32,
+    pub part: Option<u32>,
+    pub status: Status,
+}
+
+/// Compare the answers of the records to the expected ones.
+///
+/// Expected answers of a solved problem without matching record are reported
+/// as mismatches.
+pub fn verify(expected: &Expected, records: &[Record]) -> Vec<Verdict> {
+    let mut verdicts: Vec<Verdict> = Vec::new();
+    for record in records {
+        let (part, status) = match record.outcome {
+            Ok(ref answer) => (Some(answer.part), match expected.get(record.day, answer.part) {
+                Some(value) if value == answer.value => Status::Match,
+                Some(value) => Status::Mismatch {
+                    expected: String::from(value),
+                    found: Some(answer.value.clone()),
+                },
+                None => Status::Missing { found: answer.value.clone() },
+            }),
+            Err(ref message) => (None, Status::Failed(message.clone())),
+        };
+        verdicts.push(Verdict { day: record.day, part, status });
+    }
+    let mut days: Vec<u32> = records.iter().map(|record| record.day).collect();
+    days.dedup();
+    for day in days {
+        let failed = records.iter().any(|record| record.day == day && record.outcome.is_err());
+        for part in 1..3 {
+            let answered = verdicts.iter().any(|v| v.day == day && v.part == Some(part));
+            if let (Some(value), false, false) = (expected.get(day, part), answered, failed) {
+                verdicts.push(Verdict {
+                    day,
+                    part: Some(part),
+                    status: Status::Mismatch { expected: String::from(value), found: None },
+                });
+            }
+        }
+    }
+    verdicts.sort_by_key(|verdict| (verdict.day, verdict.part));
+    verdicts
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use error::Error;
+    use report::Record;
+    use solution::Answer;
+    use super::{Expected, Status, Verdict, verify};
+
+    const ANSWERS: &str = "# Expected answers\n\
+                           [01]\n\
+                           part1 = \"3\"\n\
+                           part2 = \"4\"  # wrong on purpose\n\
+                           \n\
+                           [02]\n\
+                           part2 = \"x\"\n";
+
+    fn record(day: u32, part: u32, value: &str) -> Record {
+        Record {
+            day,
+            title: "",
+            input_hash: None,
+            outcome: Ok(Answer { part, value: String::from(value), elapsed: Duration::new(0, 0) }),
+        }
+    }
+
+    #[test]
+    fn parse_answers() {
+        let expected = Expected::parse(ANSWERS).unwrap();
+        assert_eq!(expected.get(1, 1), Some("3"));
+        assert_eq!(expected.get(1, 2), Some("4"));
+        assert_eq!(expected.get(2, 1), None);
+        match Expected::parse("[01]\npart3 = \"1\"") {
+            Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), (2, 1)),
+            _ => panic!("Expected a parse error"),
+        }
+    }
+
+    #[test]
+    fn verify_answers() {
+        let expected = Expected::parse(ANSWERS).unwrap();
+        let records = vec![record(1, 1, "3"), record(1, 2, "0"), record(2, 1, "5")];
+        assert_eq!(verify(&expected, &records), vec![
+            Verdict { day: 1, part: Some(1), status: Status::Match },
+            Verdict {
+                day: 1,
+                part: Some(2),
+                status: Status::Mismatch { expected: String::from("4"), found: Some(String::from("0")) },
+            },
+            Verdict { day: 2, part: Some(1), status: Status::Missing { found: String::from("5") } },
+            Verdict {
+                day: 2,
+                part: Some(2),
+                status: Status::Mismatch { expected: String::from("x"), found: None },
+            },
+        ]);
+    }
+
+}
@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use error::{Error, Result, next_token, parse_lines, parse_token};
+use input::read_file;
+use solution::{Solver, guarded};
+
+/// Stages of the resolution of a problem that are timed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Stage {
+    Parse,
+    Part1,
+    Part2,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Stage::Parse => write!(f, "parse"),
+            Stage::Part1 => write!(f, "part1"),
+            Stage::Part2 => write!(f, "part2"),
+        }
+    }
+}
+
+impl FromStr for Stage {
+    type Err = ();
+
+    fn from_str(value: &str) -> ::std::result::Result<Stage, ()> {
+        match value {
+            "parse" => Ok(Stage::Parse),
+            "part1" => Ok(Stage::Part1),
+            "part2" => Ok(Stage::Part2),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Durations of the runs of a stage of a problem.
+#[derive(Debug, Clone)]
+pub struct Timing {
+    pub day: u32,
+    pub stage: Stage,
+    /// Durations of the runs, sorted.
+    pub runs: Vec<Duration>,
+}
+
+impl Timing {
+    pub fn min(&self) -> Duration {
+        self.runs[0]
+    }
+
+    pub fn median(&self) -> Duration {
+        self.runs[self.runs.len() / 2]
+    }
+
+    pub fn max(&self) -> Duration {
+        self.runs[self.runs.len() - 1]
+    }
+}
+
+/// Time each stage of the solvers of a day over the given number of runs.
+///
+/// When several solvers are registered for the day, their parsing times are
+/// added, while each part is timed with the solver answering it.
+pub fn bench(solvers: &[Box<Solver>], content: &str, runs: usize) -> Result<Vec<Timing>> {
+    let mut durations: HashMap<(u32, Stage), Vec<Duration>> = HashMap::new();
+    for run in 0..runs {
+        for solver in solvers {
+            let mut parse = Duration::new(0, 0);
+            let parts = guarded(|| {
+                let start = Instant::now();
+                let input = solver.parse(content)?;
+                parse = start.elapsed();
+                let start = Instant::now();
+                let first = solver.part1(&*input)?.map(|_| start.elapsed());
+                let start = Instant::now();
+                let second = solver.part2(&*input)?.map(|_| start.elapsed());
+                Ok(vec![(Stage::Part1, first), (Stage::Part2, second)])
+            })?;
+            let runs = durations.entry((solver.day(), Stage::Parse)).or_default();
+            if runs.len() > run {
+                runs[run] += parse;
+            } else {
+                runs.push(parse);
+            }
+            for (stage, elapsed) in parts {
+                if let Some(elapsed) = elapsed {
+                    durations.entry((solver.day(), stage)).or_default().push(elapsed);
+                }
+            }
+        }
+    }
+    let mut timings: Vec<Timing> = durations.into_iter()
+                                            .map(|((day, stage), mut runs)| {
+                                                runs.sort();
+                                                Timing { day, stage, runs }
+                                            })
+                                            .collect();
+    timings.sort_by_key(|timing| (timing.day, timing.stage));
+    Ok(timings)
+}
+
+/// Median durations of a previous benchmark, keyed by day and stage.
+pub type Baseline = HashMap<(u32, Stage), Duration>;
+
+/// Header line of the baseline files.
+const HEADER: &str = "day,stage,min_ns,median_ns,max_ns";
+
+/// Number of nanoseconds of a duration, as stored in the baseline files so
+/// that they are compared at the precision they were measured.
+fn nanos(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + u64::from(duration.subsec_nanos())
+}
+
+/// Save the timings as a baseline file.
+pub fn save<P: AsRef<Path>>(path: P, timings: &[Timing]) -> Result<()> {
+    let path = path.as_ref();
+    let mut content = format!("{}\n", HEADER);
+    for timing in timings {
+        content.push_str(&format!("{},{},{},{},{}\n",
+                                  timing.day,
+                                  timing.stage,
+                                  nanos(timing.min()),
+                                  nanos(timing.median()),
+                                  nanos(timing.max())));
+    }
+    File::create(path).and_then(|mut file| file.write_all(content.as_bytes()))
+                      .map_err(|err| Error::Io(path.display().to_string(), err))
+}
+
+/// Read the median durations of a baseline file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Baseline> {
+    let mut content = String::new();
+    read_file(path.as_ref().to_path_buf(), &mut content)?;
+    parse_baseline(&content)
+}
+
+/// Parse the content of a baseline file.
+pub fn parse_baseline(content: &str) -> Result<Baseline> {
+    let rows = parse_lines(content, |line| {
+        if line == HEADER || line.trim().is_empty() {
+            return Ok(None);
+        }
+        let mut tokens = line.split(',');
+        let day = parse_token::<u32>(line, next_token(line, &mut tokens)?)?;
+        let stage = parse_token::<Stage>(line, next_token(line, &mut tokens)?)?;
+        next_token(line, &mut tokens)?;
+        let nanos = parse_token::<u64>(line, next_token(line, &mut tokens)?)?;
+        let duration = Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32);
+        Ok(Some(((day, stage), duration)))
+    })?;
+    Ok(rows.into_iter().filter_map(|row| row).collect())
+}
+
+/// Relative change of the duration compared to the baseline one, in percents,
+/// if the baseline one is not zero.
+pub fn change(baseline: Duration, duration: Duration) -> Option<f64> {
+    if nanos(baseline) == 0 {
+        return None;
+    }
+    Some((nanos(duration) as f64 / nanos(baseline) as f64 - 1.0) * 100.0)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use prob_01;
+    use solution::Solver;
+    use super::{Stage, Timing, bench, change, parse_baseline};
+
+    #[test]
+    fn time_all_stages() {
+        let solvers: Vec<Box<Solver>> = vec![Box::new(prob_01::Problem)];
+        let timings = bench(&solvers, "1122", 3).unwrap();
+        let stages: Vec<(u32, Stage, usize)> = timings.iter()
+                                                      .map(|t| (t.day, t.stage, t.runs.len()))
+                                                      .collect();
+        assert_eq!(stages, vec![(1, Stage::Parse, 3), (1, Stage::Part1, 3), (1, Stage::Part2, 3)]);
+    }
+
+    #[test]
+    fn median_of_runs() {
+        let timing = Timing {
+            day: 1,
+            stage: Stage::Parse,
+            runs: vec![Duration::new(1, 0), Duration::new(2, 0), Duration::new(7, 0)],
+        };
+        assert_eq!((timing.min(), timing.median(), timing.max()),
+                   (Duration::new(1, 0), Duration::new(2, 0), Duration::new(7, 0)));
+    }
+
+    #[test]
+    fn compare_to_baseline() {
+        let baseline = parse_baseline("day,stage,min_ns,median_ns,max_ns\n\
+                                       16,part2,900000000,1000500123,1100000000\n").unwrap();
+        let median = baseline[&(16, Stage::Part2)];
+        assert_eq!(median, Duration::new(1, 500_123));
+        assert_eq!(change(median, median), Some(0.0));
+        assert_eq!(change(Duration::new(2, 0), Duration::new(1, 0)), Some(-50.0));
+        assert_eq!(change(Duration::new(0, 0), Duration::new(1, 0)), None);
+    }
+
+}
@@ -0,0 +1,137 @@
+//! Detection of the cycles of the sequences of states obtained by applying
+//! repeatedly a step function to an initial state.
+//!
+//! Brent's and Floyd's algorithms only keep a couple of states in memory, at
+//! the cost of applying the step function a few more times, while the hashed
+//! history remembers every state seen and stops at the first repetition.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Cycle of a sequence of states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cycle {
+    /// Number of states before the first one of the cycle.
+    pub prefix: usize,
+    /// Number of states in the cycle.
+    pub length: usize,
+}
+
+impl Cycle {
+    /// Number of steps leading to the same state as the given number of
+    /// steps, the smallest one.
+    pub fn reduce(&self, steps: u64) -> usize {
+        let prefix = self.prefix as u64;
+        if steps < prefix {
+            steps as usize
+        } else {
+            (prefix + (steps - prefix) % self.length as u64) as usize
+        }
+    }
+}
+
+/// Find the cycle with Brent's algorithm.
+pub fn brent<T, F>(start: &T, mut step: F) -> Cycle
+    where T: Clone + Eq,
+          F: FnMut(&T) -> T
+{
+    // Find the length, searching the hare in windows of doubling sizes
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = start.clone();
+    let mut hare = step(start);
+    while tortoise != hare {
+        if power == length {
+            tortoise = hare.clone();
+            power *= 2;
+            length = 0;
+        }
+        hare = step(&hare);
+        length += 1;
+    }
+    // Find the prefix, with the hare a length ahead of the tortoise
+    let mut tortoise = start.clone();
+    let mut hare = start.clone();
+    for _ in 0..length {
+        hare = step(&hare);
+    }
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    Cycle { prefix, length }
+}
+
+/// Find the cycle with Floyd's algorithm.
+pub fn floyd<T, F>(start: &T, mut step: F) -> Cycle
+    where T: Clone + Eq,
+          F: FnMut(&T) -> T
+{
+    // Find a state of the cycle, with the hare going twice as fast
+    let mut tortoise = step(start);
+    let mut hare = step(&tortoise);
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        let next = step(&hare);
+        hare = step(&next);
+    }
+    // Find the prefix, the distance from the start being a multiple of the
+    // length
+    let mut tortoise = start.clone();
+    let mut prefix = 0;
+    while tortoise != hare {
+        tortoise = step(&tortoise);
+        hare = step(&hare);
+        prefix += 1;
+    }
+    // Find the length, going once around the cycle
+    let mut length = 1;
+    hare = step(&tortoise);
+    while tortoise != hare {
+        hare = step(&hare);
+        length += 1;
+    }
+    Cycle { prefix, length }
+}
+
+/// Find the cycle by remembering the position of every state seen.
+pub fn hashed<T, F>(start: &T, mut step: F) -> Cycle
+    where T: Clone + Hash + Eq,
+          F: FnMut(&T) -> T
+{
+    let mut seen: HashMap<T, usize> = HashMap::new();
+    let mut state = start.clone();
+    for position in 0.. {
+        if let Some(&first) = seen.get(&state) {
+            return Cycle { prefix: first, length: position - first };
+        }
+        let next = step(&state);
+        seen.insert(state, position);
+        state = next;
+    }
+    unreachable!()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Cycle, brent, floyd, hashed};
+
+    /// Sequence `0, 1, 2, 3, 4, 5, 6, 3, 4, ...`.
+    fn step(x: &usize) -> usize {
+        if *x < 6 { x + 1 } else { 3 }
+    }
+
+    #[test]
+    fn find_cycles() {
+        let cycle = Cycle { prefix: 3, length: 4 };
+        assert_eq!(brent(&0, step), cycle);
+        assert_eq!(floyd(&0, step), cycle);
+        assert_eq!(hashed(&0, step), cycle);
+        assert_eq!(hashed(&4, step), Cycle { prefix: 0, length: 4 });
+        assert_eq!((cycle.reduce(2), cycle.reduce(9), cycle.reduce(1_000_000_000)), (2, 5, 4));
+    }
+
+}
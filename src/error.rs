@@ -0,0 +1,130 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::result;
+use std::str::FromStr;
+
+/// Errors raised while solving the problems.
+#[derive(Debug)]
+pub enum Error {
+    /// A file, such as the input of a problem, can't be read or written.
+    Io(String, io::Error),
+    /// The input is malformed, lines and columns start at 1.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// The input is well-formed but the problem has no solution for it.
+    NoSolution(String),
+    /// The solver panicked.
+    Panic(String),
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+impl Error {
+    /// Create a parse error at the given column of a single line input.
+    pub fn parse<S: Into<String>>(column: usize, message: S) -> Error {
+        Error::Parse {
+            line: 1,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// Move a parse error of a single line to the given line of the input.
+    pub fn at_line(self, line: usize) -> Error {
+        match self {
+            Error::Parse { column, message, .. } => Error::Parse { line, column, message },
+            other => other,
+        }
+    }
+
+    /// Move a parse error of a token to the position of the token in its line.
+    pub fn within(self, line: &str, token: &str) -> Error {
+        match self {
+            Error::Parse { line: l, column: c, message } => Error::Parse {
+                line: l,
+                column: column(line, token) + c - 1,
+                message,
+            },
+            other => other,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref path, ref err) => write!(f, "File {} can't be read or written: {}", path, err),
+            Error::Parse { line, column, ref message } => {
+                write!(f, "Malformed input at line {}, column {}: {}", line, column, message)
+            },
+            Error::NoSolution(ref message) => write!(f, "No solution found: {}", message),
+            Error::Panic(ref message) => write!(f, "Solver panicked: {}", message),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io(..) => "file can't be read or written",
+            Error::Parse { .. } => "malformed input",
+            Error::NoSolution(..) => "no solution found",
+            Error::Panic(..) => "solver panicked",
+        }
+    }
+}
+
+/// Column (starting at 1) of a token sliced from the given line.
+pub fn column(line: &str, token: &str) -> usize {
+    token.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+/// Take the next token of the line, reporting the end of the line if missing.
+pub fn next_token<'a, I>(line: &str, tokens: &mut I) -> Result<&'a str>
+    where I: Iterator<Item = &'a str>
+{
+    tokens.next()
+          .ok_or_else(|| Error::parse(line.len() + 1, "Unexpected end of line"))
+}
+
+/// Parse a token sliced from the given line, reporting its column on failure.
+pub fn parse_token<T: FromStr>(line: &str, token: &str) -> Result<T> {
+    token.parse::<T>()
+         .map_err(|_| Error::parse(column(line, token), format!("Invalid value `{}`", token)))
+}
+
+/// Parse each line of the content with the given function, reporting the line
+/// number of the first malformed one.
+pub fn parse_lines<T, F>(content: &str, func: F) -> Result<Vec<T>>
+    where F: Fn(&str) -> Result<T>
+{
+    content.lines()
+           .enumerate()
+           .map(|(i, line)| func(line).map_err(|err| err.at_line(i + 1)))
+           .collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Error, parse_lines, parse_token};
+
+    #[test]
+    fn parse_error_position() {
+        let content = "1 2\n3 x4\n5 6";
+        let result = parse_lines(content, |line| {
+            line.split_whitespace()
+                .map(|v| parse_token::<u32>(line, v))
+                .collect::<Result<Vec<u32>, Error>>()
+        });
+        match result {
+            Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            _ => panic!("Expected a parse error"),
+        }
+    }
+
+}
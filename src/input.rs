@@ -0,0 +1,61 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use error::{Error, Result};
+
+/// Directory containing the input files when none is given.
+pub const DEFAULT_DIR: &str = "input";
+
+/// Source of the inputs of the problems.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// Read the input from the given file, whatever the problem.
+    File(PathBuf),
+    /// Read the input from the standard input, whatever the problem.
+    Stdin,
+    /// Read the input of each problem from the file `<day>.txt` of the directory.
+    Dir(PathBuf),
+}
+
+impl Source {
+    /// Source reading from the given path, `-` standing for the standard input.
+    pub fn path(value: &str) -> Source {
+        match value {
+            "-" => Source::Stdin,
+            path => Source::File(PathBuf::from(path)),
+        }
+    }
+
+    /// Source reading the input files of the given directory.
+    pub fn dir(value: &str) -> Source {
+        Source::Dir(PathBuf::from(value))
+    }
+
+    /// Read the input of the problem of the given day.
+    pub fn read(&self, day: u32) -> Result<String> {
+        let mut content: String = String::new();
+        match *self {
+            Source::File(ref path) => read_file(path.clone(), &mut content)?,
+            Source::Dir(ref dir) => read_file(dir.join(format!("{:02}.txt", day)), &mut content)?,
+            Source::Stdin => {
+                io::stdin().read_to_string(&mut content)
+                           .map_err(|err| Error::Io(String::from("<stdin>"), err))?;
+            },
+        };
+        Ok(content)
+    }
+}
+
+impl Default for Source {
+    fn default() -> Source {
+        Source::dir(DEFAULT_DIR)
+    }
+}
+
+/// Read the file in the String variable.
+pub(crate) fn read_file(path: PathBuf, content: &mut String) -> Result<()> {
+    File::open(&path).and_then(|mut file| file.read_to_string(content))
+                     .map_err(|err| Error::Io(path.display().to_string(), err))?;
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! Knot Hash, as described by the tenth problem and used by the fourteenth.
+//!
+//! The input bytes, followed by a fixed suffix, are the lengths of the
+//! sections of a ring of numbers to reverse in turn, over several rounds. The
+//! resulting ring is then condensed into 16 bytes by xoring its blocks.
+
+use error::{Error, Result};
+
+/// Bytes appended to the input of the hash by default.
+pub const SUFFIX: [u8; 5] = [17, 31, 73, 47, 23];
+
+/// Size of the ring by default.
+pub const RING_SIZE: usize = 256;
+
+/// Number of rounds by default.
+pub const ROUNDS: usize = 64;
+
+/// Number of bytes of a digest.
+pub const DIGEST_SIZE: usize = 16;
+
+/// Tie knots in a ring of the given size, reversing the sections of the
+/// given lengths in turn, over the given number of rounds.
+pub fn knot(size: usize, lengths: &[usize], rounds: usize) -> Vec<usize> {
+    let mut ring: Vec<usize> = (0..size).collect();
+    let mut pos: usize = 0;
+    let mut skip: usize = 0;
+    for _ in 0..rounds {
+        for &length in lengths {
+            for i in 0..length / 2 {
+                ring.swap((pos + i) % size, (pos + length - 1 - i) % size);
+            }
+            pos = (pos + length + skip) % size;
+            skip += 1;
+        }
+    }
+    ring
+}
+
+/// Knot Hash of some data.
+///
+/// The data can be given in several parts, the digest being the one of their
+/// concatenation.
+#[derive(Debug, Clone)]
+pub struct KnotHasher {
+    ring_size: usize,
+    rounds: usize,
+    suffix: Vec<u8>,
+    data: Vec<u8>,
+}
+
+impl Default for KnotHasher {
+    fn default() -> KnotHasher {
+        KnotHasher::new()
+    }
+}
+
+impl KnotHasher {
+    /// Hasher with the parameters of the problems.
+    pub fn new() -> KnotHasher {
+        KnotHasher { ring_size: RING_SIZE, rounds: ROUNDS, suffix: SUFFIX.to_vec(), data: Vec::new() }
+    }
+
+    /// Use a ring of the given size, which must be a multiple of 16 between
+    /// 16 and 256, so that its numbers fit in bytes and it condenses in 16
+    /// bytes.
+    pub fn ring_size(mut self, size: usize) -> Result<KnotHasher> {
+        if size == 0 || size > 256 || size % DIGEST_SIZE != 0 {
+            return Err(Error::NoSolution(format!("a ring of {} numbers, expected a multiple of 16 up to 256",
+                                                 size)));
+        }
+        self.ring_size = size;
+        Ok(self)
+    }
+
+    pub fn rounds(mut self, rounds: usize) -> KnotHasher {
+        self.rounds = rounds;
+        self
+    }
+
+    /// Append the given bytes to the input instead of the default ones.
+    pub fn suffix(mut self, suffix: &[u8]) -> KnotHasher {
+        self.suffix = suffix.to_vec();
+        self
+    }
+
+    /// Add data to hash.
+    pub fn update(&mut self, data: &[u8]) {
+        self.data.extend_from_slice(data);
+    }
+
+    /// Digest of the data added so far.
+    pub fn digest(&self) -> [u8; DIGEST_SIZE] {
+        let lengths: Vec<usize> = self.data
+                                      .iter()
+                                      .chain(&self.suffix)
+                                      .map(|&byte| byte as usize)
+                                      .collect();
+        let ring = knot(self.ring_size, &lengths, self.rounds);
+        let mut digest = [0; DIGEST_SIZE];
+        for (byte, block) in digest.iter_mut().zip(ring.chunks(self.ring_size / DIGEST_SIZE)) {
+            *byte = block.iter().fold(0, |a, b| a ^ b) as u8;
+        }
+        digest
+    }
+
+    /// Digest of the data added so far, in hexadecimal.
+    pub fn hex_digest(&self) -> String {
+        to_hex(&self.digest())
+    }
+}
+
+/// Knot Hash of the data with the parameters of the problems.
+pub fn hash(data: &[u8]) -> [u8; DIGEST_SIZE] {
+    let mut hasher = KnotHasher::new();
+    hasher.update(data);
+    hasher.digest()
+}
+
+/// Bytes in lowercase hexadecimal.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Digest written in hexadecimal, `None` if it is not made of 32 hexadecimal
+/// digits.
+pub fn from_hex(hex: &str) -> Option<[u8; DIGEST_SIZE]> {
+    if hex.len() != 2 * DIGEST_SIZE || !hex.is_ascii() {
+        return None;
+    }
+    let mut digest = [0; DIGEST_SIZE];
+    for (i, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(digest)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{KnotHasher, from_hex, hash, knot, to_hex};
+
+    #[test]
+    fn single_round() {
+        assert_eq!(knot(5, &[3, 4, 1, 5], 1), vec![3, 4, 2, 1, 0]);
+    }
+
+    #[test]
+    fn incremental_digest() {
+        let mut hasher = KnotHasher::new();
+        hasher.update(b"AoC ");
+        hasher.update(b"2017");
+        assert_eq!(hasher.hex_digest(), "33efeb34ea91902bb2f59c9920caa6cd");
+        assert_eq!(hasher.digest(), hash(b"AoC 2017"));
+        assert_eq!(to_hex(&hash(b"")), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(from_hex("33efeb34ea91902bb2f59c9920caa6cd"), Some(hash(b"AoC 2017")));
+        assert_eq!(from_hex("33ef"), None);
+        let custom = KnotHasher::new().ring_size(16).unwrap().rounds(1).suffix(&[]);
+        assert_eq!(custom.digest(), [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15]);
+        assert!(KnotHasher::new().ring_size(0).is_err());
+        assert!(KnotHasher::new().ring_size(40).is_err());
+        assert!(KnotHasher::new().ring_size(512).is_err());
+    }
+
+}
@@ -0,0 +1,53 @@
+//! Solutions to the Advent of Code 2017.
+//!
+//! Each problem has its own module exposing the parser of its input and the
+//! solvers of its two parts, as well as a `Problem` implementing the common
+//! `Solution` interface.
+
+#![feature(iterator_step_by)]
+#![feature(entry_or_default)]
+#![feature(advanced_slice_patterns, slice_patterns)]
+
+extern crate itertools;
+
+#[macro_use(s)]
+extern crate ndarray;
+
+#[macro_use]
+extern crate nom;
+
+pub mod answers;
+pub mod bench;
+pub mod cycle;
+pub mod error;
+pub mod input;
+pub mod knot_hash;
+pub mod report;
+pub mod solution;
+pub mod vm;
+
+pub mod prob_01;
+pub mod prob_02;
+pub mod prob_03;
+pub mod prob_04;
+pub mod prob_05;
+pub mod prob_06;
+pub mod prob_07;
+pub mod prob_08;
+pub mod prob_09;
+pub mod prob_10;
+pub mod prob_11;
+pub mod prob_12;
+pub mod prob_13;
+pub mod prob_14;
+pub mod prob_15;
+pub mod prob_16;
+pub mod prob_17;
+pub mod prob_18;
+pub mod prob_18_2;
+pub mod prob_19;
+pub mod prob_20;
+pub mod prob_21;
+pub mod prob_22;
+pub mod prob_22_2;
+pub mod prob_23;
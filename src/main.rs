@@ -1,79 +1,522 @@
-#![feature(iterator_step_by)]
-#![feature(entry_or_default)]
-#![feature(advanced_slice_patterns, slice_patterns)]
+extern crate advent_of_code_2017;
 
-extern crate itertools;
+use std::env;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
 
-#[macro_use(s)]
-extern crate ndarray;
+use advent_of_code_2017::answers::{self, Expected, Status};
+use advent_of_code_2017::bench::{self, Timing};
+use advent_of_code_2017::input::Source;
+use advent_of_code_2017::prob_14::{self, Grid};
+use advent_of_code_2017::report::{self, Format, Record};
+use advent_of_code_2017::solution::{Solver, registry};
+use advent_of_code_2017::vm::{self, Proc, Sound};
+use advent_of_code_2017::vm::debug::Debugger;
+use advent_of_code_2017::vm::disasm::disassemble;
 
-#[macro_use]
-extern crate nom;
+const USAGE: &str = "Usage: advent-of-code-2017 [<day>] [--input <file>|-] [--input-dir <dir>] \
+                     [--format text|json|csv]\n       \
+                     advent-of-code-2017 verify [<day>] [--input <file>|-] [--input-dir <dir>] \
+                     [--answers <file>]\n       \
+                     advent-of-code-2017 bench [<day>] [--input <file>|-] [--input-dir <dir>] \
+                     [--runs <n>] [--save <file>] [--baseline <file>]\n       \
+                     advent-of-code-2017 debug 18|23 [--input <file>|-] [--input-dir <dir>] \
+                     [--set <reg>=<value>]... [--script <file>] [--trace <file>]\n       \
+                     advent-of-code-2017 disasm 18|23 [--input <file>|-] [--input-dir <dir>]\n       \
+                     advent-of-code-2017 render 14 [--input <file>|-] [--input-dir <dir>] \
+                     [--pbm <file>] [--ppm <file>]";
 
-use std::env;
+/// Number of runs of the benchmarks when none is given.
+const DEFAULT_RUNS: usize = 10;
+
+/// Commands of the command line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    /// Print the answers.
+    Solve,
+    /// Compare the answers to the expected ones.
+    Verify,
+    /// Time the parsing and the parts of the problems.
+    Bench,
+    /// Debug the assembly program of a problem.
+    Debug,
+    /// Print the assembly program of a problem as pseudo-code.
+    Disasm,
+    /// Draw the disk of the fourteenth problem.
+    Render,
+}
+
+/// Options given on the command line.
+struct Options {
+    command: Command,
+    day: Option<u32>,
+    source: Source,
+    format: Format,
+    answers: PathBuf,
+    runs: usize,
+    save: Option<PathBuf>,
+    baseline: Option<PathBuf>,
+    registers: Vec<(char, isize)>,
+    script: Option<PathBuf>,
+    trace: Option<PathBuf>,
+    pbm: Option<PathBuf>,
+    ppm: Option<PathBuf>,
+}
+
+/// Parse the command line arguments.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut command = Command::Solve;
+    let mut day: Option<u32> = None;
+    let mut source = Source::default();
+    let mut format = Format::Text;
+    let mut answers: Option<PathBuf> = None;
+    let mut runs: Option<usize> = None;
+    let mut save: Option<PathBuf> = None;
+    let mut baseline: Option<PathBuf> = None;
+    let mut registers: Vec<(char, isize)> = Vec::new();
+    let mut script: Option<PathBuf> = None;
+    let mut trace: Option<PathBuf> = None;
+    let mut pbm: Option<PathBuf> = None;
+    let mut ppm: Option<PathBuf> = None;
+    let mut iter = args.iter().skip(1).peekable();
+    match iter.peek().map(|arg| arg.as_str()) {
+        Some("verify") => command = Command::Verify,
+        Some("bench") => command = Command::Bench,
+        Some("debug") => command = Command::Debug,
+        Some("disasm") => command = Command::Disasm,
+        Some("render") => command = Command::Render,
+        _ => (),
+    }
+    if command != Command::Solve {
+        iter.next();
+    }
+    while let Some(arg) = iter.next() {
+        match arg.as_ref() {
+            "--input" | "-i" => match iter.next() {
+                Some(path) => source = Source::path(path),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--input-dir" | "-d" => match iter.next() {
+                Some(dir) => source = Source::dir(dir),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--format" | "-f" => match iter.next() {
+                Some(value) => format = value.parse::<Format>()?,
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--answers" | "-a" => match iter.next() {
+                Some(path) => answers = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--runs" | "-n" => match iter.next().map(|value| value.parse::<usize>()) {
+                Some(Ok(number)) if number > 0 => runs = Some(number),
+                Some(_) => return Err(String::from("The number of runs must be a positive integer")),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--save" | "-s" => match iter.next() {
+                Some(path) => save = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--baseline" | "-b" => match iter.next() {
+                Some(path) => baseline = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--set" => match iter.next() {
+                Some(value) => registers.push(parse_register(value)?),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--script" => match iter.next() {
+                Some(path) => script = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--trace" => match iter.next() {
+                Some(path) => trace = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--pbm" => match iter.next() {
+                Some(path) => pbm = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            "--ppm" => match iter.next() {
+                Some(path) => ppm = Some(PathBuf::from(path)),
+                None => return Err(format!("Missing value for {}", arg)),
+            },
+            value => match value.parse::<u32>() {
+                Ok(number) if day.is_none() => day = Some(number),
+                _ => return Err(format!("Invalid argument {}", value)),
+            },
+        }
+    }
+    if let (None, &Source::File(_)) | (None, &Source::Stdin) = (day, &source) {
+        return Err(String::from("An input file can only be given for a single problem"))
+    }
+    if command != Command::Solve && format != Format::Text {
+        return Err(String::from("The output format can only be chosen when solving"))
+    }
+    if command != Command::Verify && answers.is_some() {
+        return Err(String::from("An answers file can only be given when verifying"))
+    }
+    if command != Command::Bench && (runs.is_some() || save.is_some() || baseline.is_some()) {
+        return Err(String::from("Runs and baselines can only be given when benchmarking"))
+    }
+    if command != Command::Debug && (!registers.is_empty() || script.is_some() || trace.is_some()) {
+        return Err(String::from("Registers, scripts and traces can only be given when debugging"))
+    }
+    if (command == Command::Debug || command == Command::Disasm) && day != Some(18) && day != Some(23) {
+        return Err(String::from("Only the programs of the problems 18 and 23 can be debugged or disassembled"))
+    }
+    if command != Command::Render && (pbm.is_some() || ppm.is_some()) {
+        return Err(String::from("Images can only be written when rendering"))
+    }
+    if command == Command::Render && day != Some(14) {
+        return Err(String::from("Only the disk of the problem 14 can be rendered"))
+    }
+    if command == Command::Debug && script.is_none() && source == Source::Stdin {
+        return Err(String::from("The input can't be read from the standard input without a script"))
+    }
+    let answers = answers.unwrap_or_else(|| PathBuf::from(answers::DEFAULT_FILE));
+    let runs = runs.unwrap_or(DEFAULT_RUNS);
+    Ok(Options { command, day, source, format, answers, runs, save, baseline, registers, script, trace, pbm, ppm })
+}
 
-mod prob_01;
-mod prob_02;
-mod prob_03;
-mod prob_04;
-mod prob_05;
-mod prob_06;
-mod prob_07;
-mod prob_08;
-mod prob_09;
-mod prob_10;
-mod prob_11;
-mod prob_12;
-mod prob_13;
-mod prob_14;
-mod prob_15;
-mod prob_16;
-mod prob_17;
-mod prob_18;
-mod prob_18_2;
-mod prob_19;
-mod prob_20;
-mod prob_21;
-mod prob_22;
-mod prob_22_2;
-mod prob_23;
+/// Parse the initial value of a register, given as `<reg>=<value>`.
+fn parse_register(value: &str) -> Result<(char, isize), String> {
+    let mut parts = value.splitn(2, '=');
+    let reg = parts.next().unwrap();
+    match (reg.chars().nth(0), parts.next().map(|v| v.parse::<isize>())) {
+        (Some(name), Some(Ok(number))) if reg.len() == 1 => Ok((name, number)),
+        _ => Err(format!("Invalid register value {}, expected <reg>=<value>", value)),
+    }
+}
 
 /// Run the solver for the solutions of the Advent Of Code 2017
 fn main() {
-    let mut problem: Option<u32> = None;
     let args: Vec<String> = env::args().collect();
-    if args.len() >= 2 {
-        problem = args[1].parse::<u32>().ok();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(msg) => {
+            eprintln!("Error: {}\n{}", msg, USAGE);
+            process::exit(1)
+        }
+    };
+    let solvers: Vec<Box<Solver>> = registry().into_iter()
+                                              .filter(|solver| options.day.map_or(true, |day| solver.day() == day))
+                                              .collect();
+    if let (Some(day), true) = (options.day, solvers.is_empty()) {
+        eprintln!("Error: Solution to the problem {:02} not implemented… yet?", day);
+        process::exit(1)
+    }
+    if options.command == Command::Verify {
+        let expected = match Expected::read(&options.answers) {
+            Ok(expected) => expected,
+            Err(err) => {
+                eprintln!("Error: answers file: {}", err);
+                process::exit(1)
+            }
+        };
+        verify(&expected, &run(&solvers, &options.source))
+    }
+    if options.command == Command::Bench {
+        benchmark(&solvers, &options)
+    }
+    if options.command == Command::Debug {
+        debug(&options)
+    }
+    if options.command == Command::Disasm {
+        disasm(&options)
+    }
+    if options.command == Command::Render {
+        render(&options)
+    }
+    let records = run(&solvers, &options.source);
+    match (options.format, options.day) {
+        (Format::Text, Some(_)) => print_answers(&records),
+        (Format::Text, None) => print_table(&records),
+        (Format::Json, _) => print!("{}", report::json(&records)),
+        (Format::Csv, _) => print!("{}", report::csv(&records)),
+    }
+    let failures = records.iter().filter(|record| record.outcome.is_err()).count();
+    if failures > 0 {
+        if options.day.is_none() {
+            eprintln!("\nError: {} of the problems failed", failures);
+        }
+        process::exit(1)
+    }
+}
+
+/// Solve the problems with the given solvers, in order.
+fn run(solvers: &[Box<Solver>], source: &Source) -> Vec<Record> {
+    let mut records: Vec<Record> = Vec::new();
+    let mut last: Option<(u32, Result<String, String>)> = None;
+    for solver in solvers {
+        // Read the input only once per day, as the standard input can't be read twice
+        if last.as_ref().map_or(true, |&(day, _)| day != solver.day()) {
+            let content = source.read(solver.day()).map_err(|err| err.to_string());
+            last = Some((solver.day(), content));
+        }
+        match last {
+            Some((_, Ok(ref content))) => records.extend(report::records(&**solver, content)),
+            Some((_, Err(ref message))) => records.push(Record::failure(&**solver, None, message.clone())),
+            None => unreachable!(),
+        }
     }
+    records
+}
+
+/// Print the verification of the answers against the expected ones.
+///
+/// Exit with a non-zero code if any answer is wrong or any problem failed.
+fn verify(expected: &Expected, records: &[Record]) -> ! {
+    let verdicts = answers::verify(expected, records);
+    let mut counts: [usize; 4] = [0; 4];
+    println!("{:<3}  {:<4}  {}", "Day", "Part", "Status");
+    println!("{}", "-".repeat(3 + 2 + 4 + 2 + 32));
+    for verdict in &verdicts {
+        let part = verdict.part.map_or(String::from("-"), |part| part.to_string());
+        let status = match verdict.status {
+            Status::Match => {
+                counts[0] += 1;
+                String::from("ok")
+            },
+            Status::Mismatch { ref expected, ref found } => {
+                counts[1] += 1;
+                format!("MISMATCH: expected {}, found {}",
+                        expected,
+                        found.as_ref().map_or("nothing", |found| found.as_str()))
+            },
+            Status::Missing { ref found } => {
+                counts[2] += 1;
+                format!("missing (found {})", found)
+            },
+            Status::Failed(ref message) => {
+                counts[3] += 1;
+                format!("FAILED: {}", message)
+            },
+        };
+        println!("{:02}   {:<4}  {}", verdict.day, part, status);
+    }
+    println!("\n{} matching, {} mismatching, {} missing, {} failed",
+             counts[0], counts[1], counts[2], counts[3]);
+    process::exit(if counts[1] + counts[3] > 0 { 1 } else { 0 })
+}
+
+/// Time the stages of the problems and print their durations, compared to
+/// the baseline if any.
+fn benchmark(solvers: &[Box<Solver>], options: &Options) -> ! {
+    let baseline = match options.baseline {
+        Some(ref path) => match bench::load(path) {
+            Ok(baseline) => Some(baseline),
+            Err(err) => {
+                eprintln!("Error: baseline file: {}", err);
+                process::exit(1)
+            }
+        },
+        None => None,
+    };
+    println!("Benchmark over {} runs (durations in ms)\n", options.runs);
+    println!("{:<3}  {:<5}  {:>12}  {:>12}  {:>12}  {:>12}  {:>8}",
+             "Day", "Stage", "Min", "Median", "Max", "Baseline", "Change");
+    println!("{}", "-".repeat(3 + 2 + 5 + 5 * (2 + 12)));
+    let mut timings: Vec<Timing> = Vec::new();
+    let mut start = 0;
+    while start < solvers.len() {
+        let day = solvers[start].day();
+        let end = start + solvers[start..].iter().take_while(|solver| solver.day() == day).count();
+        let result = options.source.read(day)
+                                   .and_then(|content| bench::bench(&solvers[start..end], &content, options.runs));
+        match result {
+            Ok(day_timings) => {
+                for timing in &day_timings {
+                    let reference = baseline.as_ref().and_then(|b| b.get(&(timing.day, timing.stage)));
+                    println!("{:02}   {:<5}  {:>12}  {:>12}  {:>12}  {:>12}  {:>8}",
+                             timing.day,
+                             timing.stage.to_string(),
+                             report::millis(timing.min()),
+                             report::millis(timing.median()),
+                             report::millis(timing.max()),
+                             reference.map_or(String::from("-"), |&d| report::millis(d)),
+                             reference.and_then(|&d| bench::change(d, timing.median()))
+                                      .map_or(String::from("-"), |change| format!("{:+.1}%", change)));
+                }
+                timings.extend(day_timings);
+            },
+            Err(err) => {
+                eprintln!("Error: problem {:02}: {}", day, err);
+                process::exit(1)
+            },
+        }
+        start = end;
+    }
+    if let Some(ref path) = options.save {
+        if let Err(err) = bench::save(path, &timings) {
+            eprintln!("Error: baseline file: {}", err);
+            process::exit(1)
+        }
+    }
+    process::exit(0)
+}
+
+/// Run the debugger on the program of the problem, with commands read from
+/// the script or interactively from the standard input.
+fn debug(options: &Options) -> ! {
+    let day = options.day.unwrap();
+    let program = match options.source.read(day).and_then(|content| vm::parse(&content)) {
+        Ok(program) => program,
+        Err(err) => {
+            eprintln!("Error: problem {:02}: {}", day, err);
+            process::exit(1)
+        }
+    };
+    let mut vproc = Proc::new(Sound::default());
+    for &(reg, value) in &options.registers {
+        vproc.regs.insert(reg, value);
+    }
+    let mut debugger = Debugger::new(&program, vproc);
+    if let Some(ref path) = options.trace {
+        match File::create(path) {
+            Ok(file) => debugger.trace_to(Box::new(BufWriter::new(file))),
+            Err(err) => {
+                eprintln!("Error: trace file {} can't be created: {}", path.display(), err);
+                process::exit(1)
+            }
+        }
+    }
+    let (commands, interactive): (Box<BufRead>, bool) = match options.script {
+        Some(ref path) => match File::open(path) {
+            Ok(file) => (Box::new(BufReader::new(file)), false),
+            Err(err) => {
+                eprintln!("Error: script {} can't be read: {}", path.display(), err);
+                process::exit(1)
+            }
+        },
+        None => (Box::new(BufReader::new(io::stdin())), true),
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    if interactive {
+        write!(out, "(debug) ").and_then(|_| out.flush()).unwrap();
+    }
+    for (i, line) in commands.lines().enumerate() {
+        let line = line.unwrap_or_default();
+        match debugger.command(&line, &mut out) {
+            Ok(true) => (),
+            Ok(false) => break,
+            Err(err) if interactive => eprintln!("Error: {}", err),
+            Err(err) => {
+                eprintln!("Error: script: {}", err.at_line(i + 1));
+                if let Err(err) = debugger.finish() {
+                    eprintln!("Error: {}", err);
+                }
+                process::exit(1)
+            },
+        }
+        if interactive {
+            write!(out, "(debug) ").and_then(|_| out.flush()).unwrap();
+        }
+    }
+    // Exiting doesn't drop the debugger, so the trace must be flushed here
+    if let Err(err) = debugger.finish() {
+        eprintln!("Error: {}", err);
+        process::exit(1)
+    }
+    process::exit(0)
+}
+
+/// Print the program of the problem as pseudo-code.
+fn disasm(options: &Options) -> ! {
+    let day = options.day.unwrap();
+    match options.source.read(day).and_then(|content| vm::parse(&content)) {
+        Ok(program) => {
+            print!("{}", disassemble(&program));
+            process::exit(0)
+        },
+        Err(err) => {
+            eprintln!("Error: problem {:02}: {}", day, err);
+            process::exit(1)
+        },
+    }
+}
+
+/// Draw the disk of the fourteenth problem, as images if files are given, or
+/// on the terminal otherwise.
+fn render(options: &Options) -> ! {
+    let key = match options.source.read(14).and_then(|content| prob_14::parse(&content)) {
+        Ok(key) => key,
+        Err(err) => {
+            eprintln!("Error: problem 14: {}", err);
+            process::exit(1)
+        }
+    };
+    let grid = Grid::new(&key);
+    let regions = grid.regions();
+    let images = [(&options.pbm, prob_14::pbm(&grid)), (&options.ppm, prob_14::ppm(&regions))];
+    for &(path, ref image) in &images {
+        if let Some(ref path) = *path {
+            if let Err(err) = File::create(path).and_then(|mut file| file.write_all(image.as_bytes())) {
+                eprintln!("Error: image {} can't be written: {}", path.display(), err);
+                process::exit(1)
+            }
+        }
+    }
+    if options.pbm.is_none() && options.ppm.is_none() {
+        print!("{}", prob_14::ansi(&regions));
+    }
+    process::exit(0)
+}
+
+/// Split the records into groups of consecutive records of the same day.
+fn by_day(records: &[Record]) -> Vec<&[Record]> {
+    let mut groups: Vec<&[Record]> = Vec::new();
+    let mut start = 0;
+    for i in 1..records.len() + 1 {
+        if i == records.len() || records[i].day != records[start].day {
+            groups.push(&records[start..i]);
+            start = i;
+        }
+    }
+    groups
+}
+
+/// Print the answers of each day under a heading.
+fn print_answers(records: &[Record]) {
+    println!("Solutions to the Advent of Code 2017");
+    println!("====================================\n");
+    for group in by_day(records) {
+        println!("{}. {}:", group[0].day, group[0].title);
+        for record in group {
+            match record.outcome {
+                Ok(ref answer) if answer.part == 1 => println!("\tFirst part: {}", answer.value),
+                Ok(ref answer) => println!("\tSecond part: {}", answer.value),
+                Err(ref message) => eprintln!("Error: problem {:02}: {}", record.day, message),
+            }
+        }
+    }
+}
+
+/// Print a summary table of the answers, one row per day.
+fn print_table(records: &[Record]) {
     println!("Solutions to the Advent of Code 2017");
     println!("====================================\n");
-    match problem {
-        Some(1) => prob_01::solve(),
-        Some(2) => prob_02::solve(),
-        Some(3) => prob_03::solve(),
-        Some(4) => prob_04::solve(),
-        Some(5) => prob_05::solve(),
-        Some(6) => prob_06::solve(),
-        Some(7) => prob_07::solve(),
-        Some(8) => prob_08::solve(),
-        Some(9) => prob_09::solve(),
-        Some(10) => prob_10::solve(),
-        Some(11) => prob_11::solve(),
-        Some(12) => prob_12::solve(),
-        Some(13) => prob_13::solve(),
-        Some(14) => prob_14::solve(),
-        Some(15) => prob_15::solve(),
-        Some(16) => prob_16::solve(),
-        Some(17) => prob_17::solve(),
-        Some(18) => prob_18::solve(),
-        Some(182) => prob_18_2::solve(),
-        Some(19) => prob_19::solve(),
-        Some(20) => prob_20::solve(),
-        Some(21) => prob_21::solve(),
-        Some(22) => prob_22::solve(),
-        Some(222) => prob_22_2::solve(),
-        Some(23) => prob_23::solve(),
-        Some(_) => panic!("Solution not implemented… yet?"),
-        None => panic!("Please provide the problem number")
+    println!("{:<3}  {:<40}  {:<32}  {}", "Day", "Title", "First part", "Second part");
+    println!("{}", "-".repeat(3 + 2 + 40 + 2 + 32 + 2 + 32));
+    for group in by_day(records) {
+        let mut cells: [Option<String>; 2] = [None, None];
+        for record in group {
+            match record.outcome {
+                Ok(ref answer) => cells[answer.part as usize - 1] = Some(answer.value.clone()),
+                Err(ref message) => {
+                    let i = if cells[0].is_none() { 0 } else { 1 };
+                    cells[i] = Some(format!("FAILED: {}", message));
+                },
+            }
+        }
+        let (first, second) = (&cells[0], &cells[1]);
+        println!("{:<3}  {:<40}  {:<32}  {}",
+                 format!("{:02}", group[0].day),
+                 group[0].title,
+                 first.as_ref().map_or("-", |s| s.as_str()),
+                 second.as_ref().map_or("-", |s| s.as_str()));
     }
 }
@@ -1,37 +1,46 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-
-
-/// Solve the first problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/01.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 01 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut digits: String = String::new();
-    match file.read_to_string(&mut digits) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 01 can't be read");
-            process::exit(1)
-        }
-    };
-    // Trim trailling spaces and EOL characters
-    digits = digits.trim().to_string();
-    // Compute and print the solutions of the two parts
-    println!("1. Solutions to the first problem:");
-    println!("\tFirst part: {}", solve_first_part(&digits));
-    println!("\tSecond part: {}", solve_second_part(&digits));
+use error::{Error, Result, column};
+use solution::Solution;
+
+
+/// Solution of the first problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = String;
+
+    fn day(&self) -> u32 {
+        1
+    }
+
+    fn title(&self) -> &'static str {
+        "Inverse Captcha"
+    }
+
+    fn parse(&self, content: &str) -> Result<String> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
+}
+
+/// Parse the input of the first problem.
+pub fn parse(content: &str) -> Result<String> {
+    let digits = content.trim();
+    match digits.char_indices().find(|&(_, c)| !c.is_digit(10)) {
+        Some((i, c)) => Err(Error::parse(column(content, &digits[i..]),
+                                         format!("Invalid digit `{}`", c))),
+        None => Ok(digits.to_string()),
+    }
 }
 
 /// Solve the first part of the first problem.
-fn solve_first_part(digits: &String) -> u32 {
+pub fn solve_first_part(digits: &String) -> u32 {
     // The digit to compare is the next one (module length)
     let func = |i| i + 1;
     // Solve the generic problem with the part-specific function
@@ -39,7 +48,7 @@ fn solve_first_part(digits: &String) -> u32 {
 }
 
 /// Solve the second part of the first problem.
-fn solve_second_part(digits: &String) -> u32 {
+pub fn solve_second_part(digits: &String) -> u32 {
     // The digit to compare is the one halfway further (module length)
     let func = |i| i + digits.len() / 2;
     // Solve the generic problem with the part-specific function
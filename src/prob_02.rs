@@ -1,52 +1,53 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-
 use itertools::Itertools;
+use error::{Result, parse_lines, parse_token};
+use solution::Solution;
+
+
+/// Solution of the second problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<Vec<u32>>;
+
+    fn day(&self) -> u32 {
+        2
+    }
+
+    fn title(&self) -> &'static str {
+        "Corruption Checksum"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<Vec<u32>>> {
+        parse(content)
+    }
 
+    fn part1(&self, input: &Vec<Vec<u32>>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<Vec<u32>>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
+}
 
-/// Solve the second problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/02.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 02 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 02 can't be read");
-            process::exit(1)
-        }
-    };
-    // Trim trailling spaces and EOL characters
-    content = content.to_string();
-    let matrix : Vec<Vec<u32>> = content.lines()
-                                        .map(|line|
-                                             line.split_whitespace()
-                                                 .filter_map(|el| el.parse::<u32>().ok())
-                                                 .collect())
-                                        .collect();
-    // Compute and print the solutions of the two parts
-    println!("2. Solutions to the second problem:");
-    println!("\tFirst part: {}", solve_first_part(&matrix));
-    println!("\tSecond part: {}", solve_second_part(&matrix));
+/// Parse the input of the second problem.
+pub fn parse(content: &str) -> Result<Vec<Vec<u32>>> {
+    parse_lines(content, |line| {
+        line.split_whitespace()
+            .map(|el| parse_token::<u32>(line, el))
+            .collect()
+    })
 }
 
 /// Solve the first part of the second problem.
-fn solve_first_part(matrix: &Vec<Vec<u32>>) -> u32 {
+pub fn solve_first_part(matrix: &Vec<Vec<u32>>) -> u32 {
     matrix.iter()
           .map(|line| line.iter().max().unwrap() - line.iter().min().unwrap())
           .sum()
 }
 
 /// Solve the second part of the second second problem.
-fn solve_second_part(matrix: &Vec<Vec<u32>>) -> u32 {
+pub fn solve_second_part(matrix: &Vec<Vec<u32>>) -> u32 {
     matrix.iter()
           .map(|line|
                line.iter()
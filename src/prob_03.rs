@@ -1,90 +1,171 @@
-use std::collections::HashMap;
-
-/// Solve the third problem.
-pub fn solve() {
-    // Compute and print the solutions of the two parts
-    println!("3. Solutions to the third problem:");
-    println!("\tFirst part: {}", solve_first_part(368078));
-    println!("\tSecond part: {}", solve_second_part(368078));
+use error::{Result, parse_token};
+use solution::Solution;
+
+/// Solution of the third problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = u32;
+
+    fn day(&self) -> u32 {
+        3
+    }
+
+    fn title(&self) -> &'static str {
+        "Spiral Memory"
+    }
+
+    fn parse(&self, content: &str) -> Result<u32> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &u32) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(*input).to_string()))
+    }
+
+    fn part2(&self, input: &u32) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(u64::from(*input)).to_string()))
+    }
 }
 
-fn find_pos(value : u32) -> (i32, i32) {
-    let mut current : u32 = 1;
-    let mut i : i32 = 1;
-    loop {
-        if current == value { return (0, 0) }
-        for v in (1-i)..i {
-            current += 1;
-            if current == value { return (i, v) };
-        }
-        for v in (0-i)..(i + 1) {
-            current += 1;
-            if current == value { return (0-v, i) };
-        }
-        for v in (1-i)..i {
-            current += 1;
-            if current == value { return (0-i, 0-v) };
-        }
-        for v in (0-i)..(i + 1) {
-            current += 1;
-            if current == value { return (v, 0-i) };
-        }
-        
-        i += 1;
+/// Parse the input of the third problem.
+pub fn parse(content: &str) -> Result<u32> {
+    parse_token(content, content.trim())
+}
+
+/// Ring of the spiral containing the square of the given index, the first
+/// square being alone in the ring 0, and the ring `k` ending at the square
+/// `(2k + 1)²`.
+fn ring(index: u64) -> u64 {
+    let mut k = (((index as f64).sqrt() - 1.0) / 2.0).ceil().max(0.0) as u64;
+    while (2 * k + 1) * (2 * k + 1) < index {
+        k += 1;
     }
+    while k > 0 && (2 * k - 1) * (2 * k - 1) >= index {
+        k -= 1;
+    }
+    k
 }
 
-/// Solve the first part of the third problem.
-fn solve_first_part(value : u32) -> i32 {
-    let (x, y) = find_pos(value);
-    x.abs() + y.abs()
+/// Coordinates of the square of the given index, starting at 1.
+///
+/// The `x` axis goes right and the `y` axis up, the squares of each ring
+/// being numbered counterclockwise from the one right above the bottom right
+/// corner.
+pub fn position(index: u64) -> (i64, i64) {
+    let k = ring(index.max(1));
+    if k == 0 {
+        return (0, 0);
+    }
+    let m = index - (2 * k - 1) * (2 * k - 1) - 1;
+    let (side, t) = ((m / (2 * k)) as i64, (m % (2 * k)) as i64);
+    let k = k as i64;
+    match side {
+        0 => (k, t - k + 1),
+        1 => (k - 1 - t, k),
+        2 => (-k, k - 1 - t),
+        _ => (t - k + 1, -k),
+    }
 }
 
-fn sum_around(map : &HashMap<(i32, i32), i32>, x : i32, y : i32) -> i32 {
-    let mut total : i32 = 0;
-    for i in (0-1)..2 {
-        for j in (0-1)..2 {
-            total += map.get(&(x + i, y + j)).unwrap_or(&0);
-        }
+/// Index of the square at the given coordinates.
+pub fn index(x: i64, y: i64) -> u64 {
+    let k = x.abs().max(y.abs());
+    if k == 0 {
+        return 1;
     }
-    total
+    let m = if x == k && y > -k {
+        y + k - 1
+    } else if y == k {
+        2 * k + k - 1 - x
+    } else if x == -k {
+        4 * k + k - 1 - y
+    } else {
+        6 * k + x + k - 1
+    };
+    ((2 * k - 1) * (2 * k - 1) + 1 + m) as u64
 }
 
-/// Solve the second part of the third problem.
-fn solve_second_part(value : i32) -> i32 {
-    let mut result = HashMap::new();
-    let mut i : i32 = 1;
-    result.insert((0,0), 1);
-    loop {
-        for v in (1-i)..i {
-            let sum = sum_around(&result, i, v);
-            if sum > value { return sum };
-            result.insert((i, v), sum);
-        }
-        for v in (0-i)..(i + 1) {
-            let sum = sum_around(&result, 0-v, i);
-            if sum > value { return sum };
-            result.insert((0-v, i), sum);
-        }
-        for v in (1-i)..i {
-            let sum = sum_around(&result, 0-i, 0-v);
-            if sum > value { return sum };
-            result.insert((0-i, 0-v), sum);
-        }
-        for v in (0-i)..(i + 1) {
-            let sum = sum_around(&result, v, 0-i);
-            if sum > value { return sum };
-            result.insert((v, 0-i), sum);
-        }
-        
-        i += 1;
+/// Squares of the spiral, as their index and coordinates.
+#[derive(Debug, Clone)]
+pub struct Spiral {
+    index: u64,
+}
+
+impl Spiral {
+    pub fn new() -> Spiral {
+        Spiral { index: 1 }
+    }
+}
+
+impl Default for Spiral {
+    fn default() -> Spiral {
+        Spiral::new()
+    }
+}
+
+impl Iterator for Spiral {
+    type Item = (u64, i64, i64);
+
+    fn next(&mut self) -> Option<(u64, i64, i64)> {
+        let (x, y) = position(self.index);
+        self.index += 1;
+        Some((self.index - 1, x, y))
+    }
+}
+
+/// Values stored by the stress test, each square getting the sum of the
+/// values of its neighbours already stored.
+///
+/// The sequence ends when the values don't fit in 64 bits anymore.
+#[derive(Debug, Clone, Default)]
+pub struct StressTest {
+    /// Values of the squares, by index minus one.
+    values: Vec<u64>,
+}
+
+impl Iterator for StressTest {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let current = self.values.len() as u64 + 1;
+        let value = if current == 1 {
+            1
+        } else {
+            let (x, y) = position(current);
+            let mut total: u64 = 0;
+            for dx in -1..2 {
+                for dy in -1..2 {
+                    let neighbour = index(x + dx, y + dy);
+                    if neighbour < current {
+                        total = total.checked_add(self.values[neighbour as usize - 1])?;
+                    }
+                }
+            }
+            total
+        };
+        self.values.push(value);
+        Some(value)
     }
 }
 
+/// Solve the first part of the third problem.
+pub fn solve_first_part(value : u32) -> i32 {
+    let (x, y) = position(u64::from(value));
+    (x.abs() + y.abs()) as i32
+}
+
+/// Solve the second part of the third problem.
+///
+/// Panic if the first value larger than the given one doesn't fit in 64 bits.
+pub fn solve_second_part(value : u64) -> u64 {
+    StressTest::default().find(|&v| v > value).unwrap()
+}
+
 #[cfg(test)]
 mod tests {
 
-    use super::{solve_first_part, solve_second_part};
+    use super::{Spiral, StressTest, index, position, solve_first_part, solve_second_part};
 
     #[test]
     fn third_problem_first_part() {
@@ -100,6 +181,21 @@ mod tests {
         assert_eq!(solve_second_part(100), 122);
         assert_eq!(solve_second_part(200), 304);
         assert_eq!(solve_second_part(500), 747);
+        assert_eq!(solve_second_part(3_000_000_000), 3_813_299_996);
+    }
+
+    #[test]
+    fn third_problem_spiral() {
+        let squares: Vec<(u64, i64, i64)> = Spiral::new().take(10).collect();
+        assert_eq!(squares, vec![(1, 0, 0), (2, 1, 0), (3, 1, 1), (4, 0, 1), (5, -1, 1),
+                                 (6, -1, 0), (7, -1, -1), (8, 0, -1), (9, 1, -1), (10, 2, -1)]);
+        for (i, x, y) in Spiral::new().take(10_000) {
+            assert_eq!(index(x, y), i);
+        }
+        assert_eq!(position(1_000_000_000_000), (-499_999, 500_000));
+        assert_eq!(index(-499_999, 500_000), 1_000_000_000_000);
+        let values: Vec<u64> = StressTest::default().take(10).collect();
+        assert_eq!(values, vec![1, 1, 2, 4, 5, 10, 11, 23, 25, 26]);
     }
 
 }
@@ -1,35 +1,40 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
+use error::Result;
+use solution::Solution;
 
-/// Solve the fourth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/04.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 04 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 04 can't be read");
-            process::exit(1)
-        }
-    };
-    let passwords = content.lines().collect();
-    // Compute and print the solutions of the two parts
-    println!("4. Solutions to the fourth problem:");
-    println!("\tFirst part: {}", solve_first_part(&passwords));
-    println!("\tSecond part: {}", solve_second_part(&passwords));
+/// Solution of the fourth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = String;
+
+    fn day(&self) -> u32 {
+        4
+    }
+
+    fn title(&self) -> &'static str {
+        "High-Entropy Passphrases"
+    }
+
+    fn parse(&self, content: &str) -> Result<String> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(&input.lines().collect()).to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(&input.lines().collect()).to_string()))
+    }
+}
+
+/// Parse the input of the fourth problem.
+pub fn parse(content: &str) -> Result<String> {
+    Ok(content.to_string())
 }
 
 /// Solve the first part of the fourth problem.
-fn solve_first_part(passwords : &Vec<&str>) -> u32 {
+pub fn solve_first_part(passwords : &Vec<&str>) -> u32 {
     passwords.iter()
              .filter_map(|line| {
                   let words : Vec<&str> = line.split_whitespace().collect();
@@ -46,7 +51,7 @@ fn solve_first_part(passwords : &Vec<&str>) -> u32 {
 }
 
 /// Solve the second part of the fourth problem.
-fn solve_second_part(passwords : &Vec<&str>) -> u32 {
+pub fn solve_second_part(passwords : &Vec<&str>) -> u32 {
     passwords.iter()
              .filter_map(|line| {
                   let words : Vec<String> = line.split_whitespace()
@@ -1,35 +1,36 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-
-/// Solve the fifth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/05.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 05 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 05 can't be read");
-            process::exit(1)
-        }
-    };
-    let maze : Vec<isize> = content.lines()
-                                 .map(|line|
-                                       line.parse::<isize>()
-                                           .unwrap())
-                                 .collect();
-    // Compute and print the solutions of the two parts
-    println!("5. Solutions to the fifth problem:");
-    println!("\tFirst part: {}", solve_first_part(&mut maze.clone()));
-    println!("\tSecond part: {}", solve_second_part(&mut maze.clone()));
+use error::{Result, parse_lines, parse_token};
+use solution::Solution;
+
+/// Solution of the fifth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<isize>;
+
+    fn day(&self) -> u32 {
+        5
+    }
+
+    fn title(&self) -> &'static str {
+        "A Maze of Twisty Trampolines, All Alike"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<isize>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<isize>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(&mut input.clone()).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<isize>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(&mut input.clone()).to_string()))
+    }
+}
+
+/// Parse the input of the fifth problem.
+pub fn parse(content: &str) -> Result<Vec<isize>> {
+    parse_lines(content, |line| parse_token(line, line.trim()))
 }
 
 fn solve_generic(maze : &mut Vec<isize>, func: &Fn(isize) -> isize) -> usize {
@@ -47,11 +48,13 @@ fn solve_generic(maze : &mut Vec<isize>, func: &Fn(isize) -> isize) -> usize {
     count
 }
 
-fn solve_first_part(maze : &mut Vec<isize>) -> usize {
+/// Solve the first part of the fifth problem.
+pub fn solve_first_part(maze : &mut Vec<isize>) -> usize {
     solve_generic(maze, &|_| 1)
 }
 
-fn solve_second_part(maze : &mut Vec<isize>) -> usize {
+/// Solve the second part of the fifth problem.
+pub fn solve_second_part(maze : &mut Vec<isize>) -> usize {
     solve_generic(maze, &|i| if i >= 3 { -1 } else { 1 })
 }
 
@@ -1,77 +1,150 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-use std::collections::HashMap;
-
-/// Solve the sixth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/06.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 06 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 06 can't be read");
-            process::exit(1)
-        }
-    };
-    let banks : Vec<isize> = content.split_whitespace()
-                                    .map(|val| val.parse::<isize>().unwrap())
-                                    .collect();
-    // Compute and print the solutions of the two parts
-    println!("6. Solutions to the sixth problem:");
-    println!("\tFirst part: {}", solve_first_part(banks.clone()));
-    println!("\tSecond part: {}", solve_second_part(banks.clone()));
+use cycle::{Cycle, hashed};
+use error::{Result, parse_token};
+use solution::Solution;
+
+/// Solution of the sixth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<usize>;
+
+    fn day(&self) -> u32 {
+        6
+    }
+
+    fn title(&self) -> &'static str {
+        "Memory Reallocation"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<usize>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<usize>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input.clone()).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<usize>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input.clone()).to_string()))
+    }
 }
 
-fn solve_generic(mut banks: Vec<isize>) -> (usize, usize) {
-    let mut step = 1usize;
-    let mut seen: HashMap<Vec<isize>, usize> = HashMap::new();
-    seen.insert(banks.clone(), 0);
-    loop {
-        // Find the biggest bank position and value
-        let (p, c) = banks.clone()
-                          .into_iter()
-                          .enumerate()
-                          .max_by_key(|&(i, v)| (v, 0-i as isize))
-                          .unwrap();
-        // Set it to zero
-        banks[p] = 0;
-        // Distribute its value to next banks
-        for i in 1..c+1 {
-            let j = (p + i as usize) % banks.len();
-            banks[j] += 1;
+/// Parse the input of the sixth problem.
+pub fn parse(content: &str) -> Result<Vec<usize>> {
+    content.split_whitespace()
+           .map(|val| parse_token(content, val))
+           .collect()
+}
+
+/// Configuration of the memory banks, with the blocks of several banks packed
+/// in each word so that the configurations are cheap to store and hash.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Banks {
+    words: Vec<u64>,
+    len: usize,
+    /// Number of bits of each bank, enough to store all the blocks.
+    bits: usize,
+}
+
+impl Banks {
+    pub fn new(blocks: &[usize]) -> Banks {
+        let total: usize = blocks.iter().sum();
+        let bits = ((64 - (total as u64).leading_zeros()) as usize).max(1);
+        let per_word = 64 / bits;
+        let mut words = vec![0; (blocks.len() + per_word - 1) / per_word];
+        for (i, &value) in blocks.iter().enumerate() {
+            words[i / per_word] |= (value as u64) << (i % per_word * bits);
         }
-        // If the situation has already been seen, return the current step and the cycle
-        if seen.contains_key(&banks) {
-            return (step, step - seen[&banks])
+        Banks { words, len: blocks.len(), bits }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of blocks of a bank.
+    pub fn get(&self, index: usize) -> usize {
+        let per_word = 64 / self.bits;
+        let mask = u64::max_value() >> (64 - self.bits);
+        ((self.words[index / per_word] >> (index % per_word * self.bits)) & mask) as usize
+    }
+
+    pub fn to_vec(&self) -> Vec<usize> {
+        (0..self.len).map(|i| self.get(i)).collect()
+    }
+
+    /// Redistribute the blocks of the biggest bank, the first one if several
+    /// are the biggest.
+    ///
+    /// Each bank receives the same share of the blocks, and the remaining
+    /// ones go to the banks following the biggest one.
+    pub fn redistribute(&self) -> Banks {
+        let mut blocks = self.to_vec();
+        let n = blocks.len();
+        let (p, c) = blocks.iter()
+                           .cloned()
+                           .enumerate()
+                           .max_by_key(|&(i, v)| (v, n - i))
+                           .unwrap();
+        blocks[p] = 0;
+        for (i, bank) in blocks.iter_mut().enumerate() {
+            let distance = (i + n - p - 1) % n;
+            *bank += c / n + if distance < c % n { 1 } else { 0 };
         }
-        // Othewise store the current situation as seen
-        seen.insert(banks.clone(), step);
-        // Increase the counter
-        step += 1;
+        Banks::new(&blocks)
+    }
+}
+
+/// Configurations of the banks obtained by successive redistributions,
+/// starting with the initial one.
+pub struct States {
+    banks: Banks,
+}
+
+impl Iterator for States {
+    type Item = Vec<usize>;
+
+    fn next(&mut self) -> Option<Vec<usize>> {
+        let current = self.banks.to_vec();
+        self.banks = self.banks.redistribute();
+        Some(current)
     }
 }
 
-fn solve_first_part(banks: Vec<isize>) -> usize {
+/// Sequence of the configurations of the banks.
+pub fn states(blocks: &[usize]) -> States {
+    States { banks: Banks::new(blocks) }
+}
+
+/// Number of redistributions until a configuration is seen again, and number
+/// of redistributions between the two occurrences.
+fn solve_generic(banks: Vec<usize>) -> (usize, usize) {
+    let cycle = if banks.is_empty() {
+        Cycle { prefix: 0, length: 1 }
+    } else {
+        hashed(&Banks::new(&banks), Banks::redistribute)
+    };
+    (cycle.prefix + cycle.length, cycle.length)
+}
+
+/// Solve the first part of the sixth problem.
+pub fn solve_first_part(banks: Vec<usize>) -> usize {
     solve_generic(banks).0
 }
 
-fn solve_second_part(banks: Vec<isize>) -> usize {
+/// Solve the second part of the sixth problem.
+pub fn solve_second_part(banks: Vec<usize>) -> usize {
     solve_generic(banks).1
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{solve_first_part, solve_second_part};
+    use super::{Banks, solve_first_part, solve_second_part, states};
 
     #[test]
     fn sixth_problem_first_part() {
@@ -83,4 +156,14 @@ mod tests {
         assert_eq!(solve_second_part(vec![0, 2, 7, 0]), 4);
     }
 
+    #[test]
+    fn sixth_problem_states() {
+        let sequence: Vec<Vec<usize>> = states(&[0, 2, 7, 0]).take(6).collect();
+        assert_eq!(sequence, vec![vec![0, 2, 7, 0], vec![2, 4, 1, 2], vec![3, 1, 2, 3],
+                                  vec![0, 2, 3, 4], vec![1, 3, 4, 1], vec![2, 4, 1, 2]]);
+        let blocks: Vec<usize> = (0..40).map(|i| i * 1_000_003 % 7919).collect();
+        assert_eq!(Banks::new(&blocks).to_vec(), blocks);
+        assert_eq!(Banks::new(&[1_000_000, 0, 0]).redistribute().to_vec(), vec![333_333, 333_334, 333_333]);
+    }
+
 }
@@ -1,67 +1,109 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::{HashMap, HashSet};
 use itertools::Itertools;
+use error::{Error, Result, column, next_token, parse_lines, parse_token};
+use solution::Solution;
 
-/// Solve the seventh problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/07.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 07 not found");
-            process::exit(1)
+pub type Tower = HashMap<String, (u32, Vec<String>)>;
+
+/// Solution of the seventh problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Tower;
+
+    fn day(&self) -> u32 {
+        7
+    }
+
+    fn title(&self) -> &'static str {
+        "Recursive Circus"
+    }
+
+    fn parse(&self, content: &str) -> Result<Tower> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Tower) -> Result<Option<String>> {
+        match solve_first_part(input) {
+            Some(root) => Ok(Some(root)),
+            None => Err(Error::NoSolution(String::from("no program at the bottom of the tower"))),
         }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 07 can't be read");
-            process::exit(1)
+    }
+
+    fn part2(&self, input: &Tower) -> Result<Option<String>> {
+        match solve_second_part(input) {
+            Some(weight) => Ok(Some(weight.to_string())),
+            None => Err(Error::NoSolution(String::from("the tower is already balanced"))),
         }
-    };
-    // Compute and print the solutions of the two parts
-    println!("7. Solutions to the seventh problem:");
-    println!("\tFirst part: {}", solve_first_part(content.clone()));
-    println!("\tSecond part: {}", solve_second_part(content.clone()));
+    }
 }
 
-fn parse(content: String) -> HashMap<String, (u32, Vec<String>)> {
-    content.lines()
-           .map(|line| {
-               let mut parts = line.split_whitespace();
-               let name = String::from(parts.next().unwrap());
-               let weight = parts.next().unwrap();
-               let weight = (&weight)[1..(weight.len()-1)].parse().unwrap();
-               parts.next(); // Skip arrow
-               let rest: String = parts.collect();
-               let children: Vec<String> = rest.split(",")
-                                               .map(|v| String::from(v))
-                                               .filter(|v| !v.is_empty())
-                                               .collect();
-               (name, (weight, children))
-            })
-           .collect()
+/// Parse the input of the seventh problem.
+///
+/// Every child must have its own line and be held by a single program, so
+/// that the programs form a tower.
+pub fn parse(content: &str) -> Result<Tower> {
+    // Children are kept with their columns, to report the unknown ones
+    let programs = parse_lines(content, |line| {
+        let mut parts = line.split_whitespace();
+        let name = String::from(next_token(line, &mut parts)?);
+        let weight = next_token(line, &mut parts)?;
+        if weight.len() < 2 || !weight.starts_with('(') || !weight.ends_with(')') {
+            return Err(Error::parse(column(line, weight),
+                                    format!("Expected a weight in parentheses, found `{}`", weight)))
+        }
+        let weight: u32 = parse_token(line, &weight[1..weight.len() - 1])?;
+        let mut children: Vec<(String, usize)> = Vec::new();
+        if let Some(arrow) = parts.next() {
+            if arrow != "->" {
+                return Err(Error::parse(column(line, arrow), format!("Expected `->`, found `{}`", arrow)))
+            }
+            let list = next_token(line, &mut parts)?;
+            let list = &line[column(line, list) - 1..];
+            for child in list.split(',').map(|child| child.trim()) {
+                if child.is_empty() || !child.chars().all(char::is_alphanumeric) {
+                    return Err(Error::parse(column(line, child),
+                                            format!("Expected the name of a child, found `{}`", child)))
+                }
+                children.push((String::from(child), column(line, child)));
+            }
+        }
+        Ok((name, weight, children))
+    })?;
+    let names: HashSet<&String> = programs.iter().map(|&(ref name, _, _)| name).collect();
+    let mut held: HashSet<&String> = HashSet::new();
+    for (i, &(_, _, ref children)) in programs.iter().enumerate() {
+        for &(ref child, column) in children {
+            if !names.contains(child) {
+                return Err(Error::parse(column, format!("Unknown program `{}`", child)).at_line(i + 1))
+            }
+            if !held.insert(child) {
+                return Err(Error::parse(column, format!("Program `{}` is already held", child)).at_line(i + 1))
+            }
+        }
+    }
+    Ok(programs.iter()
+               .map(|&(ref name, weight, ref children)| {
+                   (name.clone(), (weight, children.iter().map(|&(ref child, _)| child.clone()).collect()))
+               })
+               .collect())
 }
 
-fn find_root(map: &HashMap<String, (u32, Vec<String>)>) -> String {
+fn find_root(map: &Tower) -> Option<String> {
     let children: HashSet<String> = map.values()
                                        .map(|val| val.1.clone())
                                        .flatten()
                                        .collect();
     let keys: HashSet<String> = map.keys().map(|v| String::from(v.clone())).collect();
-    keys.difference(&children).next().unwrap().clone()
+    keys.difference(&children).next().cloned()
 }
 
-fn solve_first_part(content: String) -> String {
-    let map = parse(content);
-    find_root(&map)
+/// Solve the first part of the seventh problem.
+pub fn solve_first_part(map: &Tower) -> Option<String> {
+    find_root(map)
 }
 
-fn weight_rec(val: &String, map: &HashMap<String, (u32, Vec<String>)>, weights: &mut HashMap<String, u32>) -> u32 {
+fn weight_rec(val: &String, map: &Tower, weights: &mut HashMap<String, u32>) -> u32 {
     match weights.clone().get(val) {
         Some(weight) => return *weight,
         None => {
@@ -77,9 +119,9 @@ fn weight_rec(val: &String, map: &HashMap<String, (u32, Vec<String>)>, weights:
     }
 }
 
-fn real_weights(map: &HashMap<String, (u32, Vec<String>)>) -> HashMap<String, u32>{
+fn real_weights(root: &String, map: &Tower) -> HashMap<String, u32>{
     let mut weights: HashMap<String, u32> = HashMap::new();
-    weight_rec(&find_root(map), &map, &mut weights);
+    weight_rec(root, &map, &mut weights);
     weights
 }
 
@@ -94,7 +136,7 @@ fn different(weights: &HashMap<String, u32>, elems: &Vec<String>) -> Option<(Str
     Some((min[0].clone(), *val))
 }
 
-fn find_wrong(val: &String, map: &HashMap<String, (u32, Vec<String>)>, weights: &HashMap<String, u32>) -> u32 {
+fn find_wrong(val: &String, map: &Tower, weights: &HashMap<String, u32>) -> Option<u32> {
     let &(_, ref children) = map.get(val).unwrap();
     let mut diff = different(&weights, &children);
     let mut last = None;
@@ -103,21 +145,24 @@ fn find_wrong(val: &String, map: &HashMap<String, (u32, Vec<String>)>, weights:
         let &(_, ref children) = map.get(&diff.unwrap().0).unwrap();
         diff = different(&weights, &children);
     }
-    let target_rw = last.clone().unwrap().1; 
-    let current_rw = weights.get(&last.clone().unwrap().0).unwrap();
-    let current_w = map.get(&last.clone().unwrap().0).unwrap().0;
-    current_w - (current_rw - target_rw)
+    let (name, target_rw) = last?;
+    let current_rw = weights[&name];
+    let current_w = map[&name].0;
+    // The wrong program may be too light as well as too heavy
+    (current_w + target_rw).checked_sub(current_rw)
 }
 
-fn solve_second_part(content: String) -> u32 {
-    let map = parse(content);
-    find_wrong(&find_root(&map), &map, &real_weights(&map))
+/// Solve the second part of the seventh problem.
+pub fn solve_second_part(map: &Tower) -> Option<u32> {
+    let root = find_root(map)?;
+    find_wrong(&root, map, &real_weights(&root, map))
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{solve_first_part, solve_second_part};
+    use error::Error;
+    use super::{parse, solve_first_part, solve_second_part};
 
     const INPUT : &str = "pbga (66)
                           xhth (57)
@@ -135,12 +180,29 @@ mod tests {
 
     #[test]
     fn seventh_problem_first_part() {
-        assert_eq!(solve_first_part(String::from(INPUT)), "tknk");
+        assert_eq!(solve_first_part(&parse(INPUT).unwrap()), Some(String::from("tknk")));
     }
 
     #[test]
     fn seventh_problem_second_part() {
-        assert_eq!(solve_second_part(String::from(INPUT)), 60);
+        assert_eq!(solve_second_part(&parse(INPUT).unwrap()), Some(60));
+    }
+
+    #[test]
+    fn seventh_problem_parse_errors() {
+        let errors = [("a (1) -> b, c\nb (2)", (1, 13)),
+                      ("a (", (1, 3)),
+                      ("a x12y", (1, 3)),
+                      ("a (1é)", (1, 4)),
+                      ("a (1) => b\nb (2)", (1, 7)),
+                      ("a (1) -> b,\nb (2)", (1, 12)),
+                      ("a (1) -> b\nc (1) -> b\nb (2)", (2, 10))];
+        for &(content, position) in &errors {
+            match parse(content) {
+                Err(Error::Parse { line, column, .. }) => assert_eq!((line, column), position, "{}", content),
+                _ => panic!("Expected a parse error for {}", content),
+            }
+        }
     }
 
 }
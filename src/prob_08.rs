@@ -1,8 +1,7 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashMap;
 use std::cmp::max;
+use error::{Error, Result, column, next_token, parse_lines, parse_token};
+use solution::Solution;
 
 #[derive(Debug)]
 enum Operation {
@@ -21,80 +20,87 @@ enum Comparison {
 }
 
 #[derive(Debug)]
-struct Instruction<'a> {
-    register: &'a str,
+pub struct Instruction {
+    register: String,
     operation: Operation,
     value: i32,
-    cond_register: &'a str,
+    cond_register: String,
     cond_comparison: Comparison,
     cond_value: i32
 }
 
-/// Solve the eighth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/08.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 08 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 08 can't be read");
-            process::exit(1)
-        }
-    };
-    let instructions = parse(&content);
-    // Compute and print the solutions of the two parts
-    println!("8. Solutions to the eighth problem:");
-    println!("\tFirst part: {}", solve_first_part(&instructions));
-    println!("\tSecond part: {}", solve_second_part(&instructions));
+/// Solution of the eighth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<Instruction>;
+
+    fn day(&self) -> u32 {
+        8
+    }
+
+    fn title(&self) -> &'static str {
+        "I Heard You Like Registers"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<Instruction>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<Instruction>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<Instruction>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
 }
 
-fn parse(content: &str) -> Vec<Instruction> {
-    content.lines()
-           .map(|line| {
-               let mut iter = line.trim().split_whitespace();
-               let register = iter.next().unwrap();
-               let operation = match iter.next().unwrap().as_ref() {
-                   "inc" => Operation::Inc,
-                   "dec" => Operation::Dec,
-                   _ => panic!("Wrong operation"),
-               };
-               let value = iter.next().unwrap().parse::<i32>().unwrap();
-               iter.next();
-               let cond_register = iter.next().unwrap();
-               let cond_comparison = match iter.next().unwrap().as_ref() {
-                   "<" => Comparison::Lt,
-                   "<=" => Comparison::Lte,
-                   "==" => Comparison::Equ,
-                   "!=" => Comparison::Nequ,
-                   ">=" => Comparison::Gte,
-                   ">" => Comparison::Gt,
-                   _ => panic!("Wrong comparison"),
-               };
-               let cond_value = iter.next().unwrap().parse::<i32>().unwrap();
-               Instruction {
-                   register,
-                   operation,
-                   value,
-                   cond_register,
-                   cond_comparison,
-                   cond_value
-               }
-           }).collect()
+/// Parse the input of the eighth problem.
+pub fn parse(content: &str) -> Result<Vec<Instruction>> {
+    parse_lines(content, |line| {
+        let mut iter = line.trim().split_whitespace();
+        let register = String::from(next_token(line, &mut iter)?);
+        let operation = match next_token(line, &mut iter)? {
+            "inc" => Operation::Inc,
+            "dec" => Operation::Dec,
+            op => return Err(Error::parse(column(line, op),
+                                          format!("Wrong operation `{}`", op))),
+        };
+        let value = parse_token::<i32>(line, next_token(line, &mut iter)?)?;
+        match next_token(line, &mut iter)? {
+            "if" => (),
+            other => return Err(Error::parse(column(line, other),
+                                             format!("Expected `if`, found `{}`", other))),
+        };
+        let cond_register = String::from(next_token(line, &mut iter)?);
+        let cond_comparison = match next_token(line, &mut iter)? {
+            "<" => Comparison::Lt,
+            "<=" => Comparison::Lte,
+            "==" => Comparison::Equ,
+            "!=" => Comparison::Nequ,
+            ">=" => Comparison::Gte,
+            ">" => Comparison::Gt,
+            cmp => return Err(Error::parse(column(line, cmp),
+                                           format!("Wrong comparison `{}`", cmp))),
+        };
+        let cond_value = parse_token::<i32>(line, next_token(line, &mut iter)?)?;
+        Ok(Instruction {
+            register,
+            operation,
+            value,
+            cond_register,
+            cond_comparison,
+            cond_value
+        })
+    })
 }
 
 fn solve_generic(instructions: &Vec<Instruction>) -> (i32, i32) {
     let mut registers: HashMap<String, i32> = HashMap::new();
     let mut all_max: i32 = 0;
     for instruction in instructions {
-        let cond_reg_value = match registers.get(instruction.cond_register) {
+        let cond_reg_value = match registers.get(&instruction.cond_register) {
             Some(val) => *val,
             None => 0,
         };
@@ -107,11 +113,11 @@ fn solve_generic(instructions: &Vec<Instruction>) -> (i32, i32) {
             Comparison::Gt => cond_reg_value > instruction.cond_value,
         };
         if valid {
-            let reg_value = match registers.get(instruction.register) {
+            let reg_value = match registers.get(&instruction.register) {
                 Some(val) => *val,
                 None => 0,
             };
-            let regname: String = String::from(instruction.register);
+            let regname: String = instruction.register.clone();
             match instruction.operation {
                 Operation::Inc => registers.insert(regname, reg_value + instruction.value),
                 Operation::Dec => registers.insert(regname, reg_value - instruction.value),
@@ -122,11 +128,13 @@ fn solve_generic(instructions: &Vec<Instruction>) -> (i32, i32) {
     (*registers.values().max().unwrap(), all_max)
 }
 
-fn solve_first_part(instructions: &Vec<Instruction>) -> i32 {
+/// Solve the first part of the eighth problem.
+pub fn solve_first_part(instructions: &Vec<Instruction>) -> i32 {
     solve_generic(instructions).0
 }
 
-fn solve_second_part(instructions: &Vec<Instruction>) -> i32 {
+/// Solve the second part of the eighth problem.
+pub fn solve_second_part(instructions: &Vec<Instruction>) -> i32 {
     solve_generic(instructions).1
 }
 
@@ -142,12 +150,12 @@ mod tests {
 
     #[test]
     fn eighth_problem_first_part() {
-        assert_eq!(solve_first_part(&parse(INPUT)), 1);
+        assert_eq!(solve_first_part(&parse(INPUT).unwrap()), 1);
     }
 
     #[test]
     fn eighth_problem_second_part() {
-        assert_eq!(solve_second_part(&parse(INPUT)), 10);
+        assert_eq!(solve_second_part(&parse(INPUT).unwrap()), 10);
     }
 
 }
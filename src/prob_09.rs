@@ -1,6 +1,5 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
+use error::Result;
+use solution::Solution;
 
 #[derive(Debug)]
 enum Group {
@@ -8,29 +7,36 @@ enum Group {
     Garbage,
 }
 
-/// Solve the nineth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/09.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 09 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 09 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the two parts
-    println!("9. Solutions to the nineth problem:");
-    println!("\tFirst part: {}", solve_first_part(&content));
-    println!("\tSecond part: {}", solve_second_part(&content));
+/// Solution of the nineth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = String;
+
+    fn day(&self) -> u32 {
+        9
+    }
+
+    fn title(&self) -> &'static str {
+        "Stream Processing"
+    }
+
+    fn parse(&self, content: &str) -> Result<String> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
+}
+
+/// Parse the input of the nineth problem.
+pub fn parse(content: &str) -> Result<String> {
+    Ok(content.to_string())
 }
 
 fn count_groups(input: &str, group: Group, level: u32) -> (u32, u32) {
@@ -95,11 +101,13 @@ fn count_groups(input: &str, group: Group, level: u32) -> (u32, u32) {
     return (rec.0 + val, rec.1 + gbc)
 }
 
-fn solve_first_part(input: &str) -> u32 {
+/// Solve the first part of the nineth problem.
+pub fn solve_first_part(input: &str) -> u32 {
     count_groups(input, Group::Group, 1).0
 }
 
-fn solve_second_part(input: &str) -> u32 {
+/// Solve the second part of the nineth problem.
+pub fn solve_second_part(input: &str) -> u32 {
     count_groups(input, Group::Group, 1).1
 }
 
@@ -1,89 +1,58 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-
-/// Solve the tenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/10.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 10 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 10 can't be read");
-            process::exit(1)
-        }
-    };
-    let lengths: Vec<usize> = content.trim().split(",")
-                                            .map(|v| v.parse::<usize>().unwrap())
-                                            .collect();
-    // Compute and print the solutions of the two parts
-    println!("10. Solutions to the tenth problem:");
-    println!("\tFirst part: {}", solve_first_part(256, &lengths));
-    println!("\tSecond part: {}", solve_second_part(256, &content.trim()));
-}
+use error::{Result, parse_token};
+use knot_hash::{KnotHasher, knot};
+use solution::Solution;
+
+/// Solution of the tenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = String;
 
-fn circular_slice(from: usize, to: usize, array: &Vec<usize>) -> Vec<usize> {
-    let mut result: Vec<usize> = Vec::new();
-    if from <= to {
-        result.extend_from_slice(&array[from..to]);
-    } else {
-        result.extend_from_slice(&array[from..]);
-        result.extend_from_slice(&array[..to]);
+    fn day(&self) -> u32 {
+        10
     }
-    result.reverse();
-    result
-}
 
-fn solve_first_part(size: usize, lengths: &Vec<usize>) -> usize {
-    let mut buf: Vec<usize> = (0..size).collect();
-    let mut pos: usize = 0;
-    let mut old_pos: usize = 0;
-    let mut skip: usize = 0;
-    for length in lengths {
-        for v in circular_slice(pos, (pos + length) % buf.len(), &buf) {
-            buf[pos] = v;
-            pos = (pos + 1) % buf.len();
-        }
-        pos = (old_pos + length + skip) % buf.len();
-        old_pos = pos;
-        skip += 1;
+    fn title(&self) -> &'static str {
+        "Knot Hash"
     }
-    match &buf[..] {
+
+    fn parse(&self, content: &str) -> Result<String> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(256, &lengths(input)?).to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(256, input)?))
+    }
+}
+
+/// Parse the input of the tenth problem.
+pub fn parse(content: &str) -> Result<String> {
+    Ok(content.trim().to_string())
+}
+
+fn lengths(value: &str) -> Result<Vec<usize>> {
+    value.split(",")
+         .map(|v| parse_token(value, v.trim()))
+         .collect()
+}
+
+/// Solve the first part of the tenth problem.
+pub fn solve_first_part(size: usize, lengths: &Vec<usize>) -> usize {
+    match &knot(size, lengths, 1)[..] {
         &[a, b, ..] => a * b,
         _ => panic!("Not enough elements in the list to calculate solution"),
     }
 }
 
-pub fn solve_second_part(size: usize, value: &str) -> String {
-    let mut lengths: Vec<usize> = value.chars().map(|v| format!("{}", v as u8).parse().unwrap()).collect();
-    lengths.extend_from_slice(&vec![17, 31, 73, 47, 23]);
-    let mut buf: Vec<usize> = (0..size).collect();
-    let mut pos: usize = 0;
-    let mut old_pos: usize = 0;
-    let mut skip: usize = 0;
-    for _ in 0..64 {
-        for length in &lengths {
-            for v in circular_slice(pos, (pos + length) % buf.len(), &buf) {
-                buf[pos] = v;
-                pos = (pos + 1) % buf.len();
-            }
-            pos = (old_pos + length + skip) % buf.len();
-            old_pos = pos;
-            skip += 1;
-        }
-    }
-    buf.chunks(16)
-       .map(|block| block.iter().fold(0, |a, b| a ^ b))
-       .map(|xor| format!("{:02x}", xor))
-       .collect()
+/// Solve the second part of the tenth problem.
+pub fn solve_second_part(size: usize, value: &str) -> Result<String> {
+    let mut hasher = KnotHasher::new().ring_size(size)?;
+    hasher.update(value.as_bytes());
+    Ok(hasher.hex_digest())
 }
 
 #[cfg(test)]
@@ -98,10 +67,11 @@ mod tests {
 
     #[test]
     fn tenth_problem_second_part() {
-        assert_eq!(solve_second_part(256, ""), "a2582a3a0e66e6e86e3812dcb672a272");
-        assert_eq!(solve_second_part(256, "AoC 2017"), "33efeb34ea91902bb2f59c9920caa6cd");
-        assert_eq!(solve_second_part(256, "1,2,3"), "3efbe78a8d82f29979031a4aa0b16a9d");
-        assert_eq!(solve_second_part(256, "1,2,4"), "63960835bcdc130f0b66d7ff4f6a5a8e");
+        assert_eq!(solve_second_part(256, "").unwrap(), "a2582a3a0e66e6e86e3812dcb672a272");
+        assert_eq!(solve_second_part(256, "AoC 2017").unwrap(), "33efeb34ea91902bb2f59c9920caa6cd");
+        assert_eq!(solve_second_part(256, "1,2,3").unwrap(), "3efbe78a8d82f29979031a4aa0b16a9d");
+        assert_eq!(solve_second_part(256, "1,2,4").unwrap(), "63960835bcdc130f0b66d7ff4f6a5a8e");
+        assert!(solve_second_part(5, "1,2,3").is_err());
     }
 
 }
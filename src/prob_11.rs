@@ -1,7 +1,7 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::cmp;
+use std::str::FromStr;
+use error::{Result, parse_token};
+use solution::Solution;
 
 struct Pos {
     x: isize,
@@ -45,7 +45,7 @@ impl Pos {
     }
 }
 
-enum Direction {
+pub enum Direction {
     N,
     NE,
     SE,
@@ -54,56 +54,66 @@ enum Direction {
     NW
 }
 
-impl<'a> From<&'a str> for Direction {
-    fn from(value: &'a str) -> Direction {
+impl FromStr for Direction {
+    type Err = ();
+
+    fn from_str(value: &str) -> ::std::result::Result<Direction, ()> {
         match value {
-            "n" => Direction::N,
-            "ne" => Direction::NE,
-            "se" => Direction::SE,
-            "s" => Direction::S,
-            "sw" => Direction::SW,
-            "nw" => Direction::NW,
-            _ => panic!("String {} is not a valid direction", value),
+            "n" => Ok(Direction::N),
+            "ne" => Ok(Direction::NE),
+            "se" => Ok(Direction::SE),
+            "s" => Ok(Direction::S),
+            "sw" => Ok(Direction::SW),
+            "nw" => Ok(Direction::NW),
+            _ => Err(()),
         }
     }
 }
 
-/// Solve the eleventh problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/11.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 11 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 11 can't be read");
-            process::exit(1)
-        }
-    };
-    let directions: Vec<Direction> = content.trim()
-                                            .split(",")
-                                            .map(|v| Direction::from(v))
-                                            .collect();
-    // Compute and print the solutions of the two parts
-    println!("11. Solutions to the eleventh problem:");
-    println!("\tFirst part: {}", solve_first_part(&directions));
-    println!("\tSecond part: {}", solve_second_part(&directions));
+/// Solution of the eleventh problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<Direction>;
+
+    fn day(&self) -> u32 {
+        11
+    }
+
+    fn title(&self) -> &'static str {
+        "Hex Ed"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<Direction>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<Direction>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<Direction>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
+}
+
+/// Parse the input of the eleventh problem.
+pub fn parse(content: &str) -> Result<Vec<Direction>> {
+    content.trim()
+           .split(",")
+           .map(|v| parse_token(content, v))
+           .collect()
 }
 
-fn solve_first_part(directions: &Vec<Direction>) -> usize {
+/// Solve the first part of the eleventh problem.
+pub fn solve_first_part(directions: &Vec<Direction>) -> usize {
     let mut pos: Pos = Pos { x: 0, y: 0, z: 0 };
     directions.iter().for_each(|v| pos.mv(&v));
     pos.distance()
 }
 
-fn solve_second_part(directions: &Vec<Direction>) -> usize {
+/// Solve the second part of the eleventh problem.
+pub fn solve_second_part(directions: &Vec<Direction>) -> usize {
     let mut pos: Pos = Pos { x: 0, y: 0, z: 0 };
     let mut max_dist: usize = 0;
     directions.iter().for_each(|v| {
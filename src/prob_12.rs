@@ -1,45 +1,49 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::{HashMap, HashSet};
+use error::{Error, Result, column, next_token, parse_lines};
+use solution::Solution;
 
-/// Solve the twelfth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/12.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 12 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 12 can't be read");
-            process::exit(1)
-        }
-    };
-    let pipes = parse(&content);
-    // Compute and print the solutions of the two parts
-    println!("12. Solutions to the twelfth problem:");
-    println!("\tFirst part: {}", solve_first_part(&pipes));
-    println!("\tSecond part: {}", solve_second_part(&pipes));
+/// Solution of the twelfth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = HashMap<String, Vec<String>>;
+
+    fn day(&self) -> u32 {
+        12
+    }
+
+    fn title(&self) -> &'static str {
+        "Digital Plumber"
+    }
+
+    fn parse(&self, content: &str) -> Result<HashMap<String, Vec<String>>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &HashMap<String, Vec<String>>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &HashMap<String, Vec<String>>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
 }
 
-fn parse(content: &str) -> HashMap<String, Vec<String>> {
-    content.lines()
-           .map(|line| {
-               let mut it = line.trim().split_whitespace();
-               let from = String::from(it.next().unwrap());
-               it.next();  // Skip arrow <->
-               let rest: String = it.collect();
-               let to: Vec<String> = rest.split(",").map(|v| String::from(v)).collect();
-               (from, to)
-           })
-           .collect()
+/// Parse the input of the twelfth problem.
+pub fn parse(content: &str) -> Result<HashMap<String, Vec<String>>> {
+    let pipes = parse_lines(content, |line| {
+        let mut it = line.trim().split_whitespace();
+        let from = String::from(next_token(line, &mut it)?);
+        match next_token(line, &mut it)? {
+            "<->" => (),
+            other => return Err(Error::parse(column(line, other),
+                                             format!("Expected `<->`, found `{}`", other))),
+        };
+        let rest: String = it.collect();
+        let to: Vec<String> = rest.split(",").map(|v| String::from(v)).collect();
+        Ok((from, to))
+    })?;
+    Ok(pipes.into_iter().collect())
 }
 
 fn get_group(val: &str, pipes: &HashMap<String, Vec<String>>) -> HashSet<String> {
@@ -64,11 +68,13 @@ fn get_group(val: &str, pipes: &HashMap<String, Vec<String>>) -> HashSet<String>
     set
 }
 
-fn solve_first_part(pipes: &HashMap<String, Vec<String>>) -> usize {
+/// Solve the first part of the twelfth problem.
+pub fn solve_first_part(pipes: &HashMap<String, Vec<String>>) -> usize {
     get_group("0", &pipes).len()
 }
 
-fn solve_second_part(pipes: &HashMap<String, Vec<String>>) -> usize {
+/// Solve the second part of the twelfth problem.
+pub fn solve_second_part(pipes: &HashMap<String, Vec<String>>) -> usize {
     let mut all: HashSet<String> = HashSet::new();
     for (k, v) in pipes {
         all.insert(k.clone());
@@ -104,12 +110,12 @@ mod tests {
 
     #[test]
     fn twelfth_problem_first_part() {
-        assert_eq!(solve_first_part(&parse(INPUT)), 6);
+        assert_eq!(solve_first_part(&parse(INPUT).unwrap()), 6);
     }
 
     #[test]
     fn twelfth_problem_second_part() {
-        assert_eq!(solve_second_part(&parse(INPUT)), 2);
+        assert_eq!(solve_second_part(&parse(INPUT).unwrap()), 2);
     }
 
 }
@@ -1,49 +1,56 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashMap;
+use error::{Error, Result, column, next_token, parse_lines, parse_token};
+use solution::Solution;
 
-/// Solve the thirteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/13.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 13 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 13 can't be read");
-            process::exit(1)
+/// Solution of the thirteenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = HashMap<usize, usize>;
+
+    fn day(&self) -> u32 {
+        13
+    }
+
+    fn title(&self) -> &'static str {
+        "Packet Scanners"
+    }
+
+    fn parse(&self, content: &str) -> Result<HashMap<usize, usize>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &HashMap<usize, usize>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &HashMap<usize, usize>) -> Result<Option<String>> {
+        match solve_second_part(input) {
+            Some(delay) => Ok(Some(delay.to_string())),
+            None => Err(Error::NoSolution(String::from("a layer of range 1 always catches the packet"))),
         }
-    };
-    let layers = parse(&content);
-    // Compute and print the solutions of the two parts
-    println!("13. Solutions to the thirteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(&layers));
-    println!("\tSecond part: {}", solve_second_part(&layers));
+    }
 }
 
-fn parse(content: &str) -> HashMap<usize, usize> {
-    content.lines()
-           .map(|line| {
-               let mut components = line.split(": ");
-               let layer: usize = components.next().unwrap().trim().parse().unwrap();
-               let size: usize = components.next().unwrap().trim().parse().unwrap();
-               (layer, size)
-           })
-           .collect()
+/// Parse the input of the thirteenth problem.
+pub fn parse(content: &str) -> Result<HashMap<usize, usize>> {
+    let layers = parse_lines(content, |line| {
+        let mut components = line.split(": ");
+        let layer: usize = parse_token(line, next_token(line, &mut components)?.trim())?;
+        let token = next_token(line, &mut components)?.trim();
+        let size: usize = parse_token(line, token)?;
+        if size == 0 {
+            return Err(Error::parse(column(line, token), "A layer must have a range of at least 1"))
+        }
+        Ok((layer, size))
+    })?;
+    Ok(layers.into_iter().collect())
 }
 
 fn score(delay: usize, layers: &HashMap<usize, usize>) -> (usize, Vec<usize>) {
     let cycles: Vec<usize> = layers.iter()
                                 .map(|(l, s)| {
-                                    let cycle = (*s-1) * 2;
+                                    let cycle = ((*s-1) * 2).max(1);
                                     let mut seconds: Vec<usize> = Vec::new();
                                     for i in delay..(*l+delay+1) {
                                         if i % cycle == 0 {
@@ -62,15 +69,21 @@ fn score(delay: usize, layers: &HashMap<usize, usize>) -> (usize, Vec<usize>) {
     (score, cycles.clone())
 }
 
-fn solve_first_part(layers: &HashMap<usize, usize>) -> usize {
+/// Solve the first part of the thirteenth problem.
+pub fn solve_first_part(layers: &HashMap<usize, usize>) -> usize {
     score(0, &layers).0
 }
 
-fn solve_second_part(layers: &HashMap<usize, usize>) -> usize {
+/// Solve the second part of the thirteenth problem, `None` if a layer has a
+/// range of 1, its scanner never leaving the top.
+pub fn solve_second_part(layers: &HashMap<usize, usize>) -> Option<usize> {
+    if layers.values().any(|&s| s == 1) {
+        return None;
+    }
     let mut i: usize = 0;
     loop {
         let (_, cycles) = score(i, &layers);
-        if cycles.len() == 0 { return i }
+        if cycles.len() == 0 { return Some(i) }
         i += 1;
     }
 }
@@ -87,12 +100,19 @@ mod tests {
 
     #[test]
     fn thirteenth_problem_first_part() {
-        assert_eq!(solve_first_part(&parse(INPUT)), 24);
+        assert_eq!(solve_first_part(&parse(INPUT).unwrap()), 24);
     }
 
     #[test]
     fn thirteenth_problem_second_part() {
-        assert_eq!(solve_second_part(&parse(INPUT)), 10);
+        assert_eq!(solve_second_part(&parse(INPUT).unwrap()), Some(10));
+    }
+
+    #[test]
+    fn thirteenth_problem_small_ranges() {
+        assert!(parse("0: 3\n1: 0").is_err());
+        let layers = parse("0: 3\n2: 1").unwrap();
+        assert_eq!((solve_first_part(&layers), solve_second_part(&layers)), (2, None));
     }
 
 }
@@ -1,95 +1,285 @@
-use std::fs::File;
-use std::process;
-use std::io::Read;
-use prob_10::solve_second_part as knot_hash;
-
-/// Solve the fourteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/14.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 14 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 14 can't be read");
-            process::exit(1)
+use std::fmt;
+
+use error::Result;
+use knot_hash::{DIGEST_SIZE, hash};
+use solution::Solution;
+
+/// Solution of the fourteenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = String;
+
+    fn day(&self) -> u32 {
+        14
+    }
+
+    fn title(&self) -> &'static str {
+        "Disk Defragmentation"
+    }
+
+    fn parse(&self, content: &str) -> Result<String> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input).to_string()))
+    }
+
+    fn part2(&self, input: &String) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input).to_string()))
+    }
+}
+
+/// Parse the input of the fourteenth problem.
+pub fn parse(content: &str) -> Result<String> {
+    Ok(content.trim().to_string())
+}
+
+/// Number of rows and columns of the disk.
+pub const SIZE: usize = 128;
+
+/// Squares of the disk, packed in the bytes of the hashes of the rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid {
+    rows: Vec<[u8; DIGEST_SIZE]>,
+}
+
+impl Grid {
+    /// Grid of the disk of the given key.
+    pub fn new(key: &str) -> Grid {
+        Grid::from_rows((0..SIZE).map(|i| hash(format!("{}-{}", key, i).as_bytes())).collect())
+    }
+
+    pub fn from_rows(rows: Vec<[u8; DIGEST_SIZE]>) -> Grid {
+        Grid { rows }
+    }
+
+    pub fn height(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn width(&self) -> usize {
+        8 * DIGEST_SIZE
+    }
+
+    /// Whether the square is used.
+    pub fn used(&self, row: usize, col: usize) -> bool {
+        self.rows[row][col / 8] & (0x80 >> (col % 8)) != 0
+    }
+
+    /// Number of used squares.
+    pub fn count(&self) -> usize {
+        self.rows.iter().flat_map(|row| row.iter()).map(|byte| byte.count_ones() as usize).sum()
+    }
+
+    /// Label the regions of adjacent used squares.
+    pub fn regions(&self) -> Regions {
+        let width = self.width();
+        let mut sets = UnionFind::new(self.height() * width);
+        for row in 0..self.height() {
+            for col in 0..width {
+                if !self.used(row, col) {
+                    continue;
+                }
+                if row > 0 && self.used(row - 1, col) {
+                    sets.union(row * width + col, (row - 1) * width + col);
+                }
+                if col > 0 && self.used(row, col - 1) {
+                    sets.union(row * width + col, row * width + col - 1);
+                }
+            }
         }
-    };
-    let key = content.trim();
-    // Compute and print the solutions of the two parts
-    println!("14. Solutions to the fourteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(&key));
-    println!("\tSecond part: {}", solve_second_part(&key));
-}
-
-fn solve_first_part(key: &str) -> usize {
-    (0..128).map(|i| knot_hash(256, &format!("{}-{}", &key, i)))
-            .map(|h| h.chars()
-                      .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-                      .collect::<String>())
-            .map(|b| b.chars()
-                      .filter(|c| *c == '1')
-                      .collect::<String>())
-            .map(|h| h.len())
-            .sum()
-}
-
-fn count_region(grid: &mut Vec<Vec<bool>>) -> usize {
-    let mut score = 0;
-    for i in 0..grid.len() {
-        for j in 0..grid[i].len() {
-            if grid[i][j] {
-                score += 1;
-                merge(grid, i, j);
+        let mut labels: Vec<Option<usize>> = vec![None; self.height() * width];
+        let mut sizes: Vec<usize> = Vec::new();
+        for cell in 0..labels.len() {
+            if !self.used(cell / width, cell % width) {
+                continue;
             }
+            let root = sets.find(cell);
+            let label = match labels[root] {
+                Some(label) => label,
+                None => {
+                    sizes.push(0);
+                    sizes.len() - 1
+                },
+            };
+            labels[root] = Some(label);
+            labels[cell] = Some(label);
+            sizes[label] += 1;
         }
+        Regions { width, labels, sizes }
     }
-    score
 }
 
-fn merge(grid: &mut Vec<Vec<bool>>, i: usize, j: usize) {
-    if !grid[i][j] { return }
+impl fmt::Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in 0..self.height() {
+            let line: String = (0..self.width()).map(|col| if self.used(row, col) { '#' } else { '.' }).collect();
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
 
-    grid[i][j] = false;
+/// Disjoint sets of cells, merged by size.
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
 
-    if i > 0 {
-        merge(grid, i-1, j);
+impl UnionFind {
+    fn new(len: usize) -> UnionFind {
+        UnionFind { parents: (0..len).collect(), sizes: vec![1; len] }
     }
-    if i < 127 {
-        merge(grid, i+1, j);
+
+    /// Representative of the set of the cell, halving the paths on the way.
+    fn find(&mut self, mut cell: usize) -> usize {
+        while self.parents[cell] != cell {
+            self.parents[cell] = self.parents[self.parents[cell]];
+            cell = self.parents[cell];
+        }
+        cell
     }
-    if j > 0 {
-        merge(grid, i, j-1);
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        let (small, big) = if self.sizes[a] < self.sizes[b] { (a, b) } else { (b, a) };
+        self.parents[small] = big;
+        self.sizes[big] += self.sizes[small];
     }
-    if j < 127 {
-        merge(grid, i, j+1);
+}
+
+/// Regions of a grid, labelled from zero in the order of their first square,
+/// row by row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Regions {
+    width: usize,
+    labels: Vec<Option<usize>>,
+    sizes: Vec<usize>,
+}
+
+impl Regions {
+    pub fn height(&self) -> usize {
+        self.labels.len() / self.width
     }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Label of the region of the square, `None` if it is free.
+    pub fn label(&self, row: usize, col: usize) -> Option<usize> {
+        self.labels[row * self.width + col]
+    }
+
+    /// Number of regions.
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// Number of squares of each region, by label.
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+}
+
+/// Colour of a region, as red, green and blue components.
+///
+/// The hues of consecutive labels are spread by the golden ratio, so that
+/// the colours of nearby regions are far apart.
+pub fn colour(label: usize) -> (u8, u8, u8) {
+    let hue = (label as f64 * 0.618_033_988_749_895) % 1.0 * 6.0;
+    let (value, saturation) = (0.95, 0.7);
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as usize {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let component = |c: f64| ((c + value - chroma) * 255.0).round() as u8;
+    (component(r), component(g), component(b))
 }
 
-fn solve_second_part(key: &str) -> usize {
-    let mut grid: Vec<Vec<bool>> = (0..128)
-        .map(|i| knot_hash(256, &format!("{}-{}", &key, i)))
-        .map(|h| h.chars()
-                  .map(|c| format!("{:04b}", c.to_digit(16).unwrap()))
-                  .collect::<String>())
-        .map(|b| b.chars()
-                  .map(|c| c == '1')
-                  .collect())
-        .collect();
-    count_region(&mut grid)
+/// Number of pixels per line of the PBM images, so that lines are at most 70
+/// characters long as the format requires.
+const PBM_PIXELS_PER_LINE: usize = 32;
+
+/// Number of pixels per line of the PPM images.
+const PPM_PIXELS_PER_LINE: usize = 5;
+
+/// Used squares of the grid as a plain PBM image, used squares being black.
+pub fn pbm(grid: &Grid) -> String {
+    let mut image = format!("P1\n{} {}\n", grid.width(), grid.height());
+    for row in 0..grid.height() {
+        let pixels: Vec<&str> = (0..grid.width()).map(|col| if grid.used(row, col) { "1" } else { "0" }).collect();
+        for line in pixels.chunks(PBM_PIXELS_PER_LINE) {
+            image.push_str(&line.join(" "));
+            image.push('\n');
+        }
+    }
+    image
+}
+
+/// Regions as a plain PPM image, each region in its own colour on a black
+/// background.
+pub fn ppm(regions: &Regions) -> String {
+    let mut image = format!("P3\n{} {}\n255\n", regions.width(), regions.height());
+    for row in 0..regions.height() {
+        let pixels: Vec<String> = (0..regions.width())
+            .map(|col| {
+                let (r, g, b) = regions.label(row, col).map_or((0, 0, 0), colour);
+                format!("{} {} {}", r, g, b)
+            })
+            .collect();
+        for line in pixels.chunks(PPM_PIXELS_PER_LINE) {
+            image.push_str(&line.join("  "));
+            image.push('\n');
+        }
+    }
+    image
+}
+
+/// Regions for a terminal supporting 24-bit colours, each square being two
+/// characters wide and each region in its own colour.
+pub fn ansi(regions: &Regions) -> String {
+    let mut output = String::new();
+    for row in 0..regions.height() {
+        for col in 0..regions.width() {
+            match regions.label(row, col) {
+                Some(label) => {
+                    let (r, g, b) = colour(label);
+                    output.push_str(&format!("\x1b[48;2;{};{};{}m  ", r, g, b));
+                },
+                None => output.push_str("\x1b[0m  "),
+            }
+        }
+        output.push_str("\x1b[0m\n");
+    }
+    output
+}
+
+/// Solve the first part of the fourteenth problem.
+pub fn solve_first_part(key: &str) -> usize {
+    Grid::new(key).count()
+}
+
+/// Solve the second part of the fourteenth problem.
+pub fn solve_second_part(key: &str) -> usize {
+    Grid::new(key).regions().count()
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{solve_first_part, solve_second_part};
+    use super::{Grid, ansi, colour, pbm, ppm, solve_first_part, solve_second_part};
 
     #[test]
     fn fourteenth_problem_first_part() {
@@ -101,4 +291,37 @@ mod tests {
         assert_eq!(solve_second_part("flqrgnkx"), 1242);
     }
 
+    #[test]
+    fn fourteenth_problem_regions() {
+        let grid = Grid::new("flqrgnkx");
+        let corner: Vec<String> = grid.to_string().lines().take(3).map(|line| line[..8].to_string()).collect();
+        assert_eq!(corner, vec!["##.#.#..", ".#.#.#.#", "....#.#."]);
+        let regions = grid.regions();
+        assert_eq!((regions.label(0, 0), regions.label(0, 1), regions.label(1, 1)), (Some(0), Some(0), Some(0)));
+        assert_eq!((regions.label(0, 2), regions.label(0, 3)), (None, Some(1)));
+        assert_eq!(regions.sizes().iter().sum::<usize>(), grid.count());
+    }
+
+    #[test]
+    fn fourteenth_problem_images() {
+        let mut row = [0; 16];
+        row[0] = 0b1010_0000;
+        let grid = Grid::from_rows(vec![row, [0; 16]]);
+        let regions = grid.regions();
+        assert!(pbm(&grid).starts_with("P1\n128 2\n1 0 1 0 0"));
+        let ((r0, g0, b0), (r1, g1, b1)) = (colour(0), colour(1));
+        assert_ne!((r0, g0, b0), (r1, g1, b1));
+        assert!(ppm(&regions).starts_with(&format!("P3\n128 2\n255\n{} {} {}  0 0 0  {} {} {}  0 0 0",
+                                                   r0, g0, b0, r1, g1, b1)));
+        let white = Grid::from_rows(vec![[0xFF; 16]; 2]).regions();
+        for image in &[pbm(&grid), ppm(&regions), ppm(&white)] {
+            assert!(image.lines().all(|line| line.len() <= 70));
+        }
+        assert_eq!((pbm(&grid).lines().count(), ppm(&regions).lines().count()), (2 + 2 * 4, 3 + 2 * 26));
+        let terminal = ansi(&regions);
+        assert_eq!(terminal.lines().count(), 2);
+        assert!(terminal.starts_with(&format!("\x1b[48;2;{};{};{}m  \x1b[0m  \x1b[48;2;{};{};{}m  ",
+                                              r0, g0, b0, r1, g1, b1)));
+    }
+
 }
@@ -1,70 +1,233 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
+use std::thread;
 
-const A_FACTOR: u64 = 16807;
-const B_FACTOR: u64 = 48271;
-const MODULO: u64 = 2147483647;
+use error::{Error, Result, next_token, parse_lines, parse_token};
+use solution::Solution;
 
-struct Generator {
+pub const A_FACTOR: u64 = 16807;
+pub const B_FACTOR: u64 = 48271;
+pub const MODULO: u64 = 2147483647;
+
+/// Largest modulo of the generators, so that the products of two remainders
+/// fit in 64 bits.
+pub const MAX_MODULO: u64 = 1 << 32;
+
+/// Number of values computed at once by a batched generator.
+pub const LANES: usize = 8;
+
+/// Number of threads sharing the pairs compared by the judge.
+pub const THREADS: usize = 4;
+
+/// Product of two numbers modulo another one.
+///
+/// Both numbers must be smaller than the modulo, itself at most `MAX_MODULO`.
+/// When the modulo is the Mersenne prime `2^31 - 1`, the remainder is
+/// obtained by adding the high bits of the product to its low bits instead
+/// of dividing.
+#[inline]
+pub fn mul_mod(a: u64, b: u64, modulo: u64) -> u64 {
+    debug_assert!(a < modulo && b < modulo && modulo <= MAX_MODULO);
+    let product = a * b;
+    if modulo != MODULO {
+        return product % modulo;
+    }
+    let folded = (product & MODULO) + (product >> 31);
+    let folded = (folded & MODULO) + (folded >> 31);
+    if folded >= MODULO { folded - MODULO } else { folded }
+}
+
+/// Power of a number modulo another one, at most `MAX_MODULO`, by
+/// exponentiation by squaring.
+pub fn pow_mod(base: u64, mut exponent: u64, modulo: u64) -> u64 {
+    assert!(modulo > 0 && modulo <= MAX_MODULO, "Invalid modulo {}, expected at most 2^32", modulo);
+    let mut result = 1 % modulo;
+    let mut square = base % modulo;
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = mul_mod(result, square, modulo);
+        }
+        square = mul_mod(square, square, modulo);
+        exponent >>= 1;
+    }
+    result
+}
+
+/// Generator of the values compared by the judge.
+///
+/// The values are numbered from 0, the value `n` being the base multiplied
+/// by the factor raised to the power `n + 1`, so that it can be computed
+/// without generating the previous ones.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    base: u64,
+    /// Number of the next value.
+    position: u64,
     current: u64,
     factor: u64,
     modulo: u64,
 }
 
 impl Generator {
-    fn new(base: u64, factor: u64, modulo: u64) -> Generator {
+    /// Generator with the given modulo, at most `MAX_MODULO`.
+    pub fn new(base: u64, factor: u64, modulo: u64) -> Generator {
+        assert!(modulo > 0 && modulo <= MAX_MODULO, "Invalid modulo {}, expected at most 2^32", modulo);
         Generator {
-            current: base,
-            factor,
+            base: base % modulo,
+            position: 0,
+            current: base % modulo,
+            factor: factor % modulo,
             modulo,
         }
     }
+
+    /// Number of the next value.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Go to the given value, which is the next one generated, whether it is
+    /// before or after the current position.
+    pub fn skip_to(&mut self, n: u64) {
+        self.current = mul_mod(self.base, pow_mod(self.factor, n, self.modulo), self.modulo);
+        self.position = n;
+    }
+
+    /// Value of the given number.
+    pub fn nth_value(&self, n: u64) -> u64 {
+        mul_mod(self.base, pow_mod(self.factor, n + 1, self.modulo), self.modulo)
+    }
+
+    /// Split the given number of next values into chunks of the given size,
+    /// the last one being shorter if needed.
+    ///
+    /// Each chunk is a generator starting at its first value, with the number
+    /// of values it contains, so that the chunks can be consumed
+    /// independently.
+    pub fn chunks(&self, length: u64, size: u64) -> Vec<(Generator, u64)> {
+        assert!(size > 0, "Chunks must not be empty");
+        (0..length).step_by(size as usize)
+                   .map(|start| {
+                       let mut chunk = self.clone();
+                       chunk.skip_to(self.position + start);
+                       (chunk, size.min(length - start))
+                   })
+                   .collect()
+    }
+
+    /// Generator of the same values, computing them by batches.
+    pub fn batched(mut self) -> Batched {
+        let mut lanes = [0; LANES];
+        for lane in lanes.iter_mut() {
+            *lane = self.next().unwrap();
+        }
+        Batched {
+            lanes,
+            jump: pow_mod(self.factor, LANES as u64, self.modulo),
+            modulo: self.modulo,
+            block: [0; LANES],
+            index: LANES,
+        }
+    }
 }
 
 impl Iterator for Generator {
     type Item = u64;
 
     fn next(&mut self) -> Option<u64> {
-        self.current = (self.current * self.factor) % self.modulo;
+        self.current = mul_mod(self.current, self.factor, self.modulo);
+        self.position += 1;
         Some(self.current)
     }
+
+    /// Jump directly to the value `n` places ahead.
+    fn nth(&mut self, n: usize) -> Option<u64> {
+        self.current = mul_mod(self.current, pow_mod(self.factor, n as u64, self.modulo), self.modulo);
+        self.position += n as u64;
+        self.next()
+    }
+}
+
+/// Generator computing its values by blocks of `LANES` consecutive ones.
+///
+/// Each lane jumps `LANES` values ahead at once, by multiplying its value by
+/// the factor raised to the power `LANES`. The lanes don't depend on each
+/// other, so their products are computed in parallel by the processor.
+pub struct Batched {
+    /// Values of the next block.
+    lanes: [u64; LANES],
+    jump: u64,
+    modulo: u64,
+    /// Current block, and position of its next value.
+    block: [u64; LANES],
+    index: usize,
 }
 
-/// Solve the fifteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/15.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 15 not found");
-            process::exit(1)
+impl Batched {
+    /// Next `LANES` values, following the ones of the block being iterated.
+    #[inline]
+    pub fn next_block(&mut self) -> [u64; LANES] {
+        let block = self.lanes;
+        for lane in self.lanes.iter_mut() {
+            *lane = mul_mod(*lane, self.jump, self.modulo);
         }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 15 can't be read");
-            process::exit(1)
+        block
+    }
+}
+
+impl Iterator for Batched {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<u64> {
+        if self.index == LANES {
+            self.block = self.next_block();
+            self.index = 0;
         }
-    };
-    let mut iter = content.lines();
-    let a_base: u64 = iter.next().unwrap()
-                          .split_whitespace()
-                          .nth(4).unwrap()
-                          .parse().unwrap();
-    let b_base: u64 = iter.next().unwrap()
-                          .split_whitespace()
-                          .nth(4).unwrap()
-                          .parse().unwrap();
-    // Compute and print the solutions of the two parts
-    println!("15. Solutions to the fifteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(Generator::new(a_base, A_FACTOR, MODULO),
-                                                  Generator::new(b_base, B_FACTOR, MODULO)));
-    println!("\tSecond part: {}", solve_second_part(Generator::new(a_base, A_FACTOR, MODULO),
-                                                    Generator::new(b_base, B_FACTOR, MODULO)));
+        self.index += 1;
+        Some(self.block[self.index - 1])
+    }
+}
+
+/// Solution of the fifteenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = (u64, u64);
+
+    fn day(&self) -> u32 {
+        15
+    }
+
+    fn title(&self) -> &'static str {
+        "Dueling Generators"
+    }
+
+    fn parse(&self, content: &str) -> Result<(u64, u64)> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &(u64, u64)) -> Result<Option<String>> {
+        let &(a_base, b_base) = input;
+        Ok(Some(solve_first_part(Generator::new(a_base, A_FACTOR, MODULO),
+                                 Generator::new(b_base, B_FACTOR, MODULO)).to_string()))
+    }
+
+    fn part2(&self, input: &(u64, u64)) -> Result<Option<String>> {
+        let &(a_base, b_base) = input;
+        Ok(Some(solve_second_part(Generator::new(a_base, A_FACTOR, MODULO),
+                                  Generator::new(b_base, B_FACTOR, MODULO)).to_string()))
+    }
+}
+
+/// Parse the input of the fifteenth problem.
+pub fn parse(content: &str) -> Result<(u64, u64)> {
+    let bases: Vec<u64> = parse_lines(content, |line| {
+        let mut words = line.split_whitespace().skip(4);
+        parse_token(line, next_token(line, &mut words)?)
+    })?;
+    if bases.len() < 2 {
+        return Err(Error::parse(1, "Missing the starting value of a generator").at_line(bases.len() + 1))
+    }
+    Ok((bases[0], bases[1]))
 }
 
 fn solve_generic<I, L>(mut gen_a: I, mut gen_b: L, cycles: usize) -> u64
@@ -80,15 +243,46 @@ fn solve_generic<I, L>(mut gen_a: I, mut gen_b: L, cycles: usize) -> u64
     count
 }
 
-fn solve_first_part(gen_a: Generator, gen_b: Generator) -> u64 {
-    solve_generic(gen_a,
-                  gen_b,
-                  40_000_000)
+/// Count the matching pairs of values, comparing them by blocks.
+fn solve_batched(gen_a: Generator, gen_b: Generator, cycles: usize) -> u64 {
+    let (mut gen_a, mut gen_b) = (gen_a.batched(), gen_b.batched());
+    let mut count: u64 = 0;
+    for start in (0..cycles).step_by(LANES) {
+        let (a, b) = (gen_a.next_block(), gen_b.next_block());
+        for i in 0..LANES.min(cycles - start) {
+            if a[i] & 0xFFFF == b[i] & 0xFFFF { count += 1 }
+        }
+    }
+    count
+}
+
+/// Count the matching pairs of values, sharing them between the given number
+/// of threads, at least one.
+///
+/// Each thread compares the values of the same chunks of both sequences, so
+/// the count doesn't depend on the scheduling of the threads. This doesn't
+/// apply to the second part, where the pairs are made of filtered values
+/// whose positions are unknown in advance.
+pub fn solve_parallel(gen_a: Generator, gen_b: Generator, cycles: usize, threads: usize) -> u64 {
+    // Keep the chunks a whole number of blocks long
+    let size = (cycles / threads.max(1) / LANES + 1) * LANES;
+    let handles: Vec<thread::JoinHandle<u64>> = gen_a.chunks(cycles as u64, size as u64)
+        .into_iter()
+        .zip(gen_b.chunks(cycles as u64, size as u64))
+        .map(|((a, length), (b, _))| thread::spawn(move || solve_batched(a, b, length as usize)))
+        .collect();
+    handles.into_iter().map(|handle| handle.join().unwrap()).sum()
 }
 
-fn solve_second_part(gen_a: Generator, gen_b: Generator) -> u64 {
-    solve_generic(gen_a.filter(|v| v % 4 == 0),
-                  gen_b.filter(|v| v % 8 == 0),
+/// Solve the first part of the fifteenth problem.
+pub fn solve_first_part(gen_a: Generator, gen_b: Generator) -> u64 {
+    solve_parallel(gen_a, gen_b, 40_000_000, THREADS)
+}
+
+/// Solve the second part of the fifteenth problem.
+pub fn solve_second_part(gen_a: Generator, gen_b: Generator) -> u64 {
+    solve_generic(gen_a.batched().filter(|v| v % 4 == 0),
+                  gen_b.batched().filter(|v| v % 8 == 0),
                   5_000_000)
 }
 
@@ -97,7 +291,7 @@ mod tests {
 
     use super::Generator;
     use super::{A_FACTOR, B_FACTOR, MODULO};
-    use super::{solve_first_part, solve_second_part};
+    use super::{mul_mod, pow_mod, solve_batched, solve_first_part, solve_generic, solve_parallel, solve_second_part};
 
     #[test]
     fn fifteenth_problem_first_part() {
@@ -113,4 +307,61 @@ mod tests {
                    309);
     }
 
+    #[test]
+    fn fifteenth_problem_batched() {
+        for &(a, b) in &[(0, 0), (MODULO - 1, MODULO - 1), (MODULO - 1, A_FACTOR), (123_456_789, 2_000_000_000)] {
+            assert_eq!(mul_mod(a, b, MODULO), a * b % MODULO);
+        }
+        assert_eq!(pow_mod(A_FACTOR, 5, MODULO), (0..5).fold(1, |v, _| v * A_FACTOR % MODULO));
+        let plain: Vec<u64> = (1..1001).scan(65, |v, _| {
+                                           *v = *v * A_FACTOR % MODULO;
+                                           Some(*v)
+                                       })
+                                       .collect();
+        let batched: Vec<u64> = Generator::new(65, A_FACTOR, MODULO).batched().take(1000).collect();
+        assert_eq!(batched, plain);
+        let unreduced: Vec<u64> = Generator::new(65 + 2 * MODULO, A_FACTOR, MODULO).take(1000).collect();
+        assert_eq!(unreduced, plain);
+        let other: Vec<u64> = Generator::new(65, 3, 1000).batched().take(20).collect();
+        assert_eq!(other, Generator::new(65, 3, 1000).take(20).collect::<Vec<u64>>());
+        assert_eq!(solve_batched(Generator::new(65, A_FACTOR, MODULO), Generator::new(8921, B_FACTOR, MODULO), 1003),
+                   solve_generic(Generator::new(65, A_FACTOR, MODULO), Generator::new(8921, B_FACTOR, MODULO), 1003));
+    }
+
+    #[test]
+    fn fifteenth_problem_jump_ahead() {
+        let values: Vec<u64> = Generator::new(65, A_FACTOR, MODULO).take(100).collect();
+        let mut generator = Generator::new(65, A_FACTOR, MODULO);
+        assert_eq!((generator.nth_value(0), generator.nth_value(99)), (1092455, values[99]));
+        assert_eq!(generator.nth(41), Some(values[41]));
+        generator.skip_to(10);
+        assert_eq!((generator.position(), generator.next()), (10, Some(values[10])));
+        let chunks = generator.chunks(25, 10);
+        assert_eq!(chunks.iter().map(|&(ref chunk, length)| (chunk.position(), length)).collect::<Vec<_>>(),
+                   vec![(11, 10), (21, 10), (31, 5)]);
+        let chunked: Vec<u64> = chunks.into_iter().flat_map(|(chunk, length)| chunk.take(length as usize)).collect();
+        assert_eq!(chunked, values[11..36].to_vec());
+        for &threads in &[0, 1, 3, 8] {
+            assert_eq!(solve_parallel(Generator::new(65, A_FACTOR, MODULO),
+                                      Generator::new(8921, B_FACTOR, MODULO),
+                                      100_003,
+                                      threads),
+                       solve_generic(Generator::new(65, A_FACTOR, MODULO),
+                                     Generator::new(8921, B_FACTOR, MODULO),
+                                     100_003));
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Chunks must not be empty")]
+    fn fifteenth_problem_empty_chunks() {
+        Generator::new(65, A_FACTOR, MODULO).chunks(25, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid modulo")]
+    fn fifteenth_problem_large_modulo() {
+        Generator::new(65, A_FACTOR, (1 << 32) + 1);
+    }
+
 }
@@ -1,36 +1,38 @@
-use std::fs::File;
-use std::io::Read;
 use std::ops::{Index, IndexMut};
-use std::process;
+use cycle::hashed;
+use error::{Error, Result, next_token, parse_token};
+use solution::Solution;
 
-const NB_PROGS: usize = 16;
+/// Number of programs dancing.
+pub const NB_PROGS: usize = 16;
+
+/// Number of dances of the second part.
+pub const NB_DANCES: u64 = 1_000_000_000;
 
 #[derive(Debug)]
 struct Buffer {
-    progs: [char; NB_PROGS],
+    progs: Vec<char>,
     shift: usize,
 }
 
 impl Index<usize> for Buffer {
     type Output = char;
     fn index<'a>(&'a self, index: usize) -> &'a char {
-        &self.progs[(self.shift + index) % NB_PROGS]
+        &self.progs[(self.shift + index) % self.progs.len()]
     }
 }
 
 impl IndexMut<usize> for Buffer {
     fn index_mut<'a>(&'a mut self, index: usize) -> &'a mut char {
-        &mut self.progs[(self.shift + index) % NB_PROGS]
+        let len = self.progs.len();
+        &mut self.progs[(self.shift + index) % len]
     }
 }
 
 impl Buffer {
-    fn new() -> Buffer {
-        Buffer { progs: progs(), shift: 0 }
-    }
-
     fn shift(&mut self, size: usize) {
-        self.shift = (self.shift + (NB_PROGS - size)) % NB_PROGS;
+        let len = self.progs.len();
+        self.shift = (self.shift + (len - size % len)) % len;
     }
 
     fn swap(&mut self, a: usize, b: usize) {
@@ -49,7 +51,7 @@ impl Buffer {
     
     fn content(&mut self) -> String {
         let mut result: String = String::new();
-        for i in 0..NB_PROGS {
+        for i in 0..self.progs.len() {
             result.push(self[i])
         }
         result
@@ -57,69 +59,94 @@ impl Buffer {
 }
 
 #[derive(Debug)]
-enum Operation {
+pub enum Operation {
     Spin(usize),
     Exchange(usize, usize),
     Partner(char, char),
 }
 
 impl Operation {
-    fn new(val: &str) -> Operation {
-        let mut iter = val.chars();
-        match iter.next().unwrap() {
-            's' => {
-                let num: usize = iter.collect::<String>().parse().unwrap();   
-                Operation::Spin(num)
+    pub fn new(val: &str) -> Result<Operation> {
+        match val.chars().next() {
+            Some('s') => {
+                let num: usize = parse_token(val, &val[1..])?;
+                Ok(Operation::Spin(num))
             },
-            'x' => {
-                let rest: String = iter.collect();
-                let parts: Vec<&str> = rest.split("/").collect();
-                Operation::Exchange(parts[0].parse().unwrap(), parts[1].parse().unwrap())
+            Some('x') => {
+                let mut parts = val[1..].split("/");
+                let a = parse_token(val, next_token(val, &mut parts)?)?;
+                let b = parse_token(val, next_token(val, &mut parts)?)?;
+                Ok(Operation::Exchange(a, b))
             },
-            'p' => {
-                let rest: String = iter.collect();
-                let parts: Vec<&str> = rest.split("/").collect();
-                Operation::Partner(parts[0].chars().nth(0).unwrap(), parts[1].chars().nth(0).unwrap())
+            Some('p') => {
+                let mut parts = val[1..].split("/");
+                let a = parse_token(val, next_token(val, &mut parts)?)?;
+                let b = parse_token(val, next_token(val, &mut parts)?)?;
+                Ok(Operation::Partner(a, b))
             },
-            _ => panic!("Malformed input")
+            Some(c) => Err(Error::parse(1, format!("Unknown dance move `{}`", c))),
+            None => Err(Error::parse(1, "Missing dance move")),
         }
     }
-}
 
-/// Solve the sixteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/16.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 16 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 16 can't be read");
-            process::exit(1)
+    /// Check that the move can be done in a line of the given number of
+    /// programs.
+    fn check(&self, size: usize) -> Result<()> {
+        let names = progs(size);
+        match *self {
+            Operation::Spin(a) if a > size => {
+                Err(Error::NoSolution(format!("spin of {} programs in a line of {}", a, size)))
+            },
+            Operation::Exchange(a, b) if a >= size || b >= size => {
+                Err(Error::NoSolution(format!("exchange of position {} in a line of {} programs",
+                                              a.max(b), size)))
+            },
+            Operation::Partner(a, b) if !names.contains(&a) || !names.contains(&b) => {
+                Err(Error::NoSolution(format!("partner `{}` not in a line of {} programs",
+                                              if names.contains(&a) { b } else { a }, size)))
+            },
+            _ => Ok(()),
         }
-    };
-    let operations: Vec<Operation> = content.split(",")
-                                            .map(|v| Operation::new(v))
-                                            .collect();
-    // Compute and print the solutions of the two parts
-    println!("16. Solutions to the sixteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(&operations));
-    println!("\tSecond part: {}", solve_second_part(&operations));
+    }
 }
 
-fn progs() -> [char; NB_PROGS] {
-    let mut progs: [char; NB_PROGS] = ['a'; NB_PROGS];
-    for i in 0..NB_PROGS {
-        progs[i] = (97 + i as u8) as char;
+/// Solution of the sixteenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<Operation>;
+
+    fn day(&self) -> u32 {
+        16
+    }
+
+    fn title(&self) -> &'static str {
+        "Permutation Promenade"
     }
-    progs
+
+    fn parse(&self, content: &str) -> Result<Vec<Operation>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<Operation>) -> Result<Option<String>> {
+        solve_first_part(input).map(Some)
+    }
+
+    fn part2(&self, input: &Vec<Operation>) -> Result<Option<String>> {
+        solve_second_part(input).map(Some)
+    }
+}
+
+/// Parse the input of the sixteenth problem.
+pub fn parse(content: &str) -> Result<Vec<Operation>> {
+    content.trim()
+           .split(",")
+           .map(|v| Operation::new(v).map_err(|err| err.within(content, v)))
+           .collect()
+}
+
+fn progs(size: usize) -> Vec<char> {
+    (0..size).map(|i| (97 + i as u8) as char).collect()
 }
 
 fn apply(buffer: &mut Buffer, operation: &Operation) {
@@ -130,39 +157,85 @@ fn apply(buffer: &mut Buffer, operation: &Operation) {
     }
 }
 
-fn solve_generic(operations: &Vec<Operation>, cycles: u64) -> String {
-    let mut buffer = Buffer::new();
-    for cycle in 0..cycles {
-        if cycle % 1_000_000 == 0 { println!("{}", cycle) }
+/// Dance the given number of times, with the given number of programs, from
+/// 1 to 26.
+///
+/// The dance always leads from a line to the same next one, so the lines
+/// repeat after a while, and the final line is the one at the same position
+/// in the cycle.
+pub fn dance(operations: &Vec<Operation>, size: usize, cycles: u64) -> Result<String> {
+    if size == 0 || size > 26 {
+        return Err(Error::NoSolution(format!("a line of {} programs, expected 1 to 26", size)));
+    }
+    for operation in operations {
+        operation.check(size)?;
+    }
+    let step = |progs: &Vec<char>| {
+        let mut buffer = Buffer { progs: progs.clone(), shift: 0 };
         for operation in operations {
             apply(&mut buffer, operation);
         }
+        buffer.content().chars().collect()
+    };
+    let start = progs(size);
+    let mut progs = start.clone();
+    for _ in 0..hashed(&start, &step).reduce(cycles) {
+        progs = step(&progs);
     }
-    buffer.content()
+    Ok(progs.into_iter().collect())
 }
 
-fn solve_first_part(operations: &Vec<Operation>) -> String {
-    solve_generic(operations, 1)
+/// Solve the first part of the sixteenth problem.
+pub fn solve_first_part(operations: &Vec<Operation>) -> Result<String> {
+    dance(operations, NB_PROGS, 1)
 }
 
-fn solve_second_part(operations: &Vec<Operation>) -> String {
-    // They choose 1_000_000_000 cycles to force us to not apply the brut force.
-    // I'm not following rules B-)
-    solve_generic(operations, 1_000_000_000)
+/// Solve the second part of the sixteenth problem.
+pub fn solve_second_part(operations: &Vec<Operation>) -> Result<String> {
+    dance(operations, NB_PROGS, NB_DANCES)
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::{Operation, solve_first_part};
+    use error::Error;
+    use super::{Operation, dance};
 
     #[test]
     fn sixteenth_problem_first_part() {
-        let content = "s2,x3/4,pe/b";
+        let content = "s1,x3/4,pe/b";
         let operations: Vec<Operation> = content.split(",")
-                                                .map(|v| Operation::new(v))
+                                                .map(|v| Operation::new(v).unwrap())
                                                 .collect();
-        assert_eq!(solve_first_part(&operations), "baedc");
+        assert_eq!(dance(&operations, 5, 1).unwrap(), "baedc");
+    }
+
+    #[test]
+    fn sixteenth_problem_second_part() {
+        let content = "s1,x3/4,pe/b";
+        let operations: Vec<Operation> = content.split(",")
+                                                .map(|v| Operation::new(v).unwrap())
+                                                .collect();
+        assert_eq!(dance(&operations, 5, 2).unwrap(), "ceadb");
+        assert_eq!(dance(&operations, 5, 1_000_000_000).unwrap(), "abcde");
+    }
+
+    #[test]
+    fn sixteenth_problem_line_length() {
+        let content = "s1,x3/4,pe/b";
+        let operations: Vec<Operation> = content.split(",")
+                                                .map(|v| Operation::new(v).unwrap())
+                                                .collect();
+        for &size in &[0, 3, 4, 27] {
+            match dance(&operations, size, 1) {
+                Err(Error::NoSolution(_)) => (),
+                other => panic!("Expected no solution with {} programs, got {:?}", size, other),
+            }
+        }
+        assert!(dance(&vec![Operation::new("pe/b").unwrap()], 3, 1).is_err());
+        let spin = vec![Operation::new("s6").unwrap()];
+        assert!(dance(&spin, 5, 1).is_err());
+        assert_eq!(dance(&spin, 6, 1).unwrap(), "abcdef");
     }
 
 }
@@ -1,12 +1,40 @@
-/// Solve the seventeenth problem.
-pub fn solve() {
-    // Compute and print the solutions of the two parts
-    println!("17. Solutions to the seventeenth problem:");
-    println!("\tFirst part: {}", solve_first_part(348));
-    println!("\tSecond part: {}", solve_second_part(348));
+use error::{Result, parse_token};
+use solution::Solution;
+
+/// Solution of the seventeenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = usize;
+
+    fn day(&self) -> u32 {
+        17
+    }
+
+    fn title(&self) -> &'static str {
+        "Spinlock"
+    }
+
+    fn parse(&self, content: &str) -> Result<usize> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &usize) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(*input).to_string()))
+    }
+
+    fn part2(&self, input: &usize) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(*input).to_string()))
+    }
+}
+
+/// Parse the input of the seventeenth problem.
+pub fn parse(content: &str) -> Result<usize> {
+    parse_token(content, content.trim())
 }
 
-fn solve_first_part(step: usize) -> usize {
+/// Solve the first part of the seventeenth problem.
+pub fn solve_first_part(step: usize) -> usize {
     let mut buffer: Vec<usize> = vec![0];
     let mut pos: usize = 0;
 
@@ -18,7 +46,8 @@ fn solve_first_part(step: usize) -> usize {
     buffer[pos+1]
 }
 
-fn solve_second_part(step: usize) -> usize {
+/// Solve the second part of the seventeenth problem.
+pub fn solve_second_part(step: usize) -> usize {
     let mut buflen = 1;
     let mut pos = 0;
     let mut result = 0;
@@ -1,185 +1,57 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
-use std::collections::HashMap;
+use error::{Error, Result};
+use solution::Solution;
+use vm::{self, Instruction, Sound, State, fault};
+use vm::compile::{Machine, compile};
 
-type Program = Vec<Instruction>;
+/// Solution of the eighteenth problem.
+pub struct Problem;
 
-#[derive(Debug, Clone)]
-enum Val {
-    Int(isize),
-    Reg(char),
-}
-
-impl Val {
-    fn new(value: &str) -> Val {
-        match value.chars().nth(0) {
-            Some(v) if v.is_digit(10) || v == '-' => Val::Int(value.parse::<isize>().unwrap()),
-            Some(_) => Val::Reg(value.chars().nth(0).unwrap()),
-            None => panic!("Malformed input"),
-        }
-    }
+impl Solution for Problem {
+    type Input = Vec<Instruction>;
 
-    fn value(&self, vproc: &Proc) -> isize {
-        match *self {
-            Val::Int(v) => v,
-            Val::Reg(v) => *vproc.regs.get(&v).unwrap_or(&0),
-        }
+    fn day(&self) -> u32 {
+        18
     }
-}
-
-#[derive(Debug, Clone)]
-enum Instruction {
-    Snd(Val),
-    Set(Val, Val),
-    Add(Val, Val),
-    Mul(Val, Val),
-    Mod(Val, Val),
-    Rcv(Val),
-    Jgz(Val, Val),
-}
 
-impl Instruction {
-    fn new(value: &str) -> Instruction {
-        match value.chars().take(3).collect::<String>().as_ref() {
-            p@"snd" | p@"rcv" => {
-                let val = Val::new(value.chars().skip(4).collect::<String>().trim().as_ref());
-                match p {
-                    "snd" => Instruction::Snd(val),
-                    "rcv" => Instruction::Rcv(val),
-                    _ => panic!("Malformed input"),
-                }
-            },
-            p@"set" | p@"add" | p@"mul" | p@"mod" | p@"jgz" => {
-                let rest = value.chars().skip(4).collect::<String>();
-                let parts: Vec<&str> = rest.trim().split(" ").collect();
-                let val_a = Val::new(parts[0]);
-                let val_b = Val::new(parts[1]);
-                match p {
-                    "set" => Instruction::Set(val_a, val_b),
-                    "add" => Instruction::Add(val_a, val_b),
-                    "mul" => Instruction::Mul(val_a, val_b),
-                    "mod" => Instruction::Mod(val_a, val_b),
-                    "jgz" => Instruction::Jgz(val_a, val_b),
-                    _ => panic!("Malformed input"),
-                }
-            },
-            p@_ => panic!("Malformed input {}", p)
-        }
+    fn title(&self) -> &'static str {
+        "Duet"
     }
 
-    fn execute(self, vproc: &mut Proc) -> bool {
-        match self {
-            Instruction::Snd(a) => {
-                vproc.last = Some(a.value(vproc));
-                true
-            },
-            Instruction::Set(a, b) => {
-                if let Val::Reg(reg) = a {
-                    let val = b.value(vproc);
-                    vproc.regs.insert(reg, val);
-                }
-                true
-            },
-            Instruction::Add(a, b) => {
-                if let Val::Reg(reg) = a {
-                    let val = a.value(vproc) + b.value(vproc);
-                    vproc.regs.insert(reg, val);
-                }
-                true
-            },
-            Instruction::Mul(a, b) => {
-                if let Val::Reg(reg) = a {
-                    let val = a.value(vproc) * b.value(vproc);
-                    vproc.regs.insert(reg, val);
-                }
-                true
-            },
-            Instruction::Mod(a, b) => {
-                if let Val::Reg(reg) = a {
-                    let val = a.value(vproc) % b.value(vproc);
-                    vproc.regs.insert(reg, val);
-                }
-                true
-            },
-            Instruction::Rcv(a) => {
-                if a.value(vproc) != 0 { return false }
-                true
-            },
-            Instruction::Jgz(a, b) => {
-                if a.value(vproc) <= 0 { return true }
-                let new_pc = vproc.pc as isize + b.value(vproc);
-                vproc.pc = new_pc as usize - 1;
-                new_pc >= 0 || new_pc < vproc.instructions.len() as isize
-            },
-        }
+    fn parse(&self, content: &str) -> Result<Vec<Instruction>> {
+        parse(content)
     }
-}
-
-#[derive(Debug, Clone)]
-struct Proc {
-    instructions: Program,
-    pc: usize,
-    regs: HashMap<char, isize>,
-    last: Option<isize>,
-}
 
-impl Proc {
-    fn new(instructions: Program) -> Proc {
-        Proc {
-            instructions,
-            pc: 0,
-            regs: HashMap::new(),
-            last: None
+    fn part1(&self, input: &Vec<Instruction>) -> Result<Option<String>> {
+        match solve_first_part(input)? {
+            Some(frequency) => Ok(Some(frequency.to_string())),
+            None => Err(Error::NoSolution(String::from("no sound played before recovering"))),
         }
     }
 
-    fn run(&mut self) -> bool {
-        if self.pc >= self.instructions.len() { return false }
-        let instruction = self.instructions[self.pc as usize].clone();
-        self.pc = self.pc + 1;
-        let v = instruction.execute(self);
-        v
+    fn part2(&self, _input: &Vec<Instruction>) -> Result<Option<String>> {
+        Ok(None)
     }
 }
 
-/// Solve the eighteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/18.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 18 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 18 can't be read");
-            process::exit(1)
-        }
-    };
-    let instructions = content
-        .lines()
-        .map(|line| Instruction::new(line.trim()))
-        .collect();
-    // Compute and print the solutions of the two parts
-    println!("18. Solutions to the eighteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(&instructions));
+/// Parse the input of the eighteenth problem.
+pub fn parse(content: &str) -> Result<Vec<Instruction>> {
+    vm::parse(content)
 }
 
-fn solve_first_part(instructions: &Vec<Instruction>) -> isize {
-    let mut vproc = Proc::new((*instructions).clone());
-    while vproc.run() {}
-    vproc.last.unwrap()
+/// Solve the first part of the eighteenth problem.
+pub fn solve_first_part(instructions: &Vec<Instruction>) -> Result<Option<isize>> {
+    let code = compile(instructions);
+    let mut machine = Machine::new(&code, Sound::default());
+    if machine.run() == State::Fault {
+        return Err(fault(machine.pc));
+    }
+    Ok(machine.io.last)
 }
 
 #[cfg(test)]
 mod tests {
 
+    use vm;
     use super::{Instruction, solve_first_part};
 
     const CONTENT: &str = "set a 1
@@ -196,9 +68,10 @@ mod tests {
     #[test]
     fn eighteenth_problem_first_part() {
         let instructions = CONTENT.lines()
-                                  .map(|line| Instruction::new(line.trim()))
+                                  .map(|line| Instruction::new(line.trim()).unwrap())
                                   .collect();
-        assert_eq!(solve_first_part(&instructions), 4);
+        assert_eq!(solve_first_part(&instructions).unwrap(), Some(4));
+        assert!(solve_first_part(&vm::parse("snd 1\nmod a 0\nrcv a").unwrap()).is_err());
     }
 
 }
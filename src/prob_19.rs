@@ -1,8 +1,7 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
+use error::Result;
+use solution::Solution;
 
-type Maze = Vec<Vec<char>>;
+pub type Maze = Vec<Vec<char>>;
 
 enum Direction {
     Up,
@@ -38,32 +37,35 @@ impl Direction {
     }
 }
 
-/// Solve the nineteenth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/19.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 19 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 19 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the two parts
-    println!("19. Solutions to the nineteenth problem:");
-    println!("\tFirst part: {}", solve_first_part(parse(&content)));
-    println!("\tSecond part: {}", solve_second_part(parse(&content)));
+/// Solution of the nineteenth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Maze;
+
+    fn day(&self) -> u32 {
+        19
+    }
+
+    fn title(&self) -> &'static str {
+        "A Series of Tubes"
+    }
+
+    fn parse(&self, content: &str) -> Result<Maze> {
+        Ok(parse(content))
+    }
+
+    fn part1(&self, input: &Maze) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input.clone())))
+    }
+
+    fn part2(&self, input: &Maze) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input.clone()).to_string()))
+    }
 }
 
-fn parse(input: &str) -> Maze {
+/// Parse the input of the nineteenth problem.
+pub fn parse(input: &str) -> Maze {
     input.lines()
          .map(|line| line.chars().collect())
          .collect()
@@ -98,11 +100,13 @@ fn solve_generic(maze: Maze) -> (String, usize) {
     (txt, count)
 }
 
-fn solve_first_part(maze: Maze) -> String {
+/// Solve the first part of the nineteenth problem.
+pub fn solve_first_part(maze: Maze) -> String {
     solve_generic(maze).0
 }
 
-fn solve_second_part(maze: Maze) -> usize {
+/// Solve the second part of the nineteenth problem.
+pub fn solve_second_part(maze: Maze) -> usize {
     solve_generic(maze).1
 }
 
@@ -1,14 +1,14 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashMap;
+use std::str::FromStr;
 use itertools::Itertools;
-use nom::digit;
+use nom::{IResult, digit, multispace};
+use error::{Error, Result, column, parse_lines};
+use solution::Solution;
 
 const CYCLES: usize = 1_000;
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct Vector {
+pub struct Vector {
     x: isize,
     y: isize,
     z: isize,
@@ -21,7 +21,7 @@ impl Vector {
 }
 
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
-struct Particle {
+pub struct Particle {
     pos: Vector,
     vel: Vector,
     acc: Vector,
@@ -42,54 +42,90 @@ impl Particle {
     }
 }
 
-named!(number(&str) -> isize, alt!(recognize!(pair!(opt!(tag!("-")), call!(digit))) => {|s: &str| s.parse().unwrap()}));
-named!(xyz(&str) -> Vector, map!(separated_list!(tag!(","), number), |l| Vector::new(l[0], l[1], l[2])));
-named!(vector(&str) -> Vector, delimited!(char!('<'), xyz, char!('>')));
-named!(particle(&str) -> Particle, ws!(do_parse!(
-    tag!("p=") >>
-    p: vector >>
-    tag!(", v=") >>
-    v: vector >>
-    tag!(", a=") >>
-    a: vector >>
-    (Particle {
-        pos: p,
-        vel: v,
-        acc: a,
-    }))));
-
-/// Solve the twentieth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/20.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 20 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 20 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the two parts
-    println!("20. Solutions to the twentieth problem:");
-    println!("\tFirst part: {}", solve_first_part(parse(&content)));
-    println!("\tSecond part: {}", solve_second_part(parse(&content)));
+named!(number(&str) -> isize, map_res!(recognize!(pair!(opt!(char!('-')), digit)), FromStr::from_str));
+named!(space(&str) -> Option<&str>, opt!(complete!(multispace)));
+
+/// Parse the next token of the line, after the whitespaces, reporting an
+/// error at its position if it is not the expected one.
+fn token<'a, T, F>(line: &str, rest: &mut &'a str, parser: F, expected: &str) -> Result<T>
+    where F: Fn(&'a str) -> IResult<&'a str, T>
+{
+    if let IResult::Done(remaining, _) = space(rest) {
+        *rest = remaining;
+    }
+    match parser(rest) {
+        IResult::Done(remaining, value) => {
+            *rest = remaining;
+            Ok(value)
+        },
+        _ => Err(Error::parse(column(line, rest), format!("Expected {}", expected))),
+    }
+}
+
+/// Parse a vector `<x,y,z>`.
+fn vector(line: &str, rest: &mut &str) -> Result<Vector> {
+    let integer = "an integer fitting in an `isize`";
+    token(line, rest, |i| tag!(i, "<"), "`<`")?;
+    let x = token(line, rest, number, integer)?;
+    token(line, rest, |i| tag!(i, ","), "`,`")?;
+    let y = token(line, rest, number, integer)?;
+    token(line, rest, |i| tag!(i, ","), "`,`")?;
+    let z = token(line, rest, number, integer)?;
+    token(line, rest, |i| tag!(i, ">"), "`>`")?;
+    Ok(Vector::new(x, y, z))
+}
+
+/// Parse a particle `p=<x,y,z>, v=<x,y,z>, a=<x,y,z>`.
+fn particle(line: &str) -> Result<Particle> {
+    let mut rest = line;
+    token(line, &mut rest, |i| tag!(i, "p="), "`p=`")?;
+    let pos = vector(line, &mut rest)?;
+    token(line, &mut rest, |i| tag!(i, ","), "`,`")?;
+    token(line, &mut rest, |i| tag!(i, "v="), "`v=`")?;
+    let vel = vector(line, &mut rest)?;
+    token(line, &mut rest, |i| tag!(i, ","), "`,`")?;
+    token(line, &mut rest, |i| tag!(i, "a="), "`a=`")?;
+    let acc = vector(line, &mut rest)?;
+    match rest.trim() {
+        "" => Ok(Particle { pos, vel, acc }),
+        extra => Err(Error::parse(column(line, extra), "Expected the end of the line")),
+    }
+}
+
+/// Solution of the twentieth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Vec<Particle>;
+
+    fn day(&self) -> u32 {
+        20
+    }
+
+    fn title(&self) -> &'static str {
+        "Particle Swarm"
+    }
+
+    fn parse(&self, content: &str) -> Result<Vec<Particle>> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Vec<Particle>) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input.clone()).to_string()))
+    }
+
+    fn part2(&self, input: &Vec<Particle>) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input.clone()).to_string()))
+    }
 }
 
-fn parse(input: &str) -> Vec<Particle> {
-    input.lines()
-         .map(|line| particle(line).to_result().unwrap())
-         .collect()
+/// Parse the input of the twentieth problem.
+pub fn parse(input: &str) -> Result<Vec<Particle>> {
+    parse_lines(input, particle)
 }
 
-fn solve_first_part(mut particles: Vec<Particle>) -> usize {
+/// Solve the first part of the twentieth problem.
+pub fn solve_first_part(mut particles: Vec<Particle>) -> usize {
     for _ in 0..CYCLES {
         for mut particle in &mut particles {
             particle.next()
@@ -120,7 +156,8 @@ fn eliminate_duplicates(particles: &mut Vec<Particle>) {
     }
 }
 
-fn solve_second_part(mut particles: Vec<Particle>) -> usize {
+/// Solve the second part of the twentieth problem.
+pub fn solve_second_part(mut particles: Vec<Particle>) -> usize {
     for _ in 0..CYCLES {
         eliminate_duplicates(&mut particles);
         for mut particle in &mut particles {
@@ -133,6 +170,7 @@ fn solve_second_part(mut particles: Vec<Particle>) -> usize {
 #[cfg(test)]
 mod tests {
 
+    use error::Error;
     use super::{parse, solve_first_part, solve_second_part};
 
     const INPUT: &str = "p=<3,0,0>, v=<2,0,0>, a=<-1,0,0>
@@ -140,7 +178,7 @@ mod tests {
 
     #[test]
     fn twentieth_problem_first_part() {
-        assert_eq!(solve_first_part(parse(INPUT)), 0);
+        assert_eq!(solve_first_part(parse(INPUT).unwrap()), 0);
     }
 
     const INPUT_2: &str = "p=<-6,0,0>, v=<3,0,0>, a=<0,0,0>
@@ -150,7 +188,22 @@ mod tests {
 
     #[test]
     fn twentieth_problem_second_part() {
-        assert_eq!(solve_second_part(parse(INPUT_2)), 1);
+        assert_eq!(solve_second_part(parse(INPUT_2).unwrap()), 1);
+    }
+
+    #[test]
+    fn twentieth_problem_parse_errors() {
+        let errors = ["p=<99999999999999999999,0,0>, v=<0,0,0>, a=<0,0,0>",
+                      "p=<1,0,0>, v=<0,0>, a=<0,0,0>",
+                      "p=<1,0,0>, v=<0,0,0> a=<0,0,0>",
+                      "p=<1,0,0>, v=<0,0,0>, a=<0,0,0> x"];
+        let columns: Vec<usize> = errors.iter()
+                                        .map(|line| match parse(line) {
+                                            Err(Error::Parse { column, .. }) => column,
+                                            _ => panic!("Expected a parse error for {}", line),
+                                        })
+                                        .collect();
+        assert_eq!(columns, vec![4, 18, 22, 33]);
     }
 
 }
@@ -1,43 +1,45 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashMap;
 use itertools::Itertools;
 use ndarray::{Array2, arr2};
+use error::{Error, Result, column, parse_lines};
+use solution::Solution;
 
-type Mat = Array2<usize>;
-type Rules = HashMap<Mat, Mat>;
+pub type Mat = Array2<usize>;
+pub type Rules = HashMap<Mat, Mat>;
 
 named!(matrix(&str) -> Vec<&str>, separated_list_complete!(char!('/'), is_a!(".#")));
 named!(rule(&str) -> (Vec<&str>, Vec<&str>), ws!(separated_pair!(matrix, tag!("=>"), matrix)));
 
-/// Solve the twenty-oneth problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/21.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 21 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 21 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the two parts
-    println!("21. Solutions to the twenty-oneth problem:");
-    println!("\tFirst part: {}", solve_generic(parse(&content), 5));
-    println!("\tSecond part: {}", solve_generic(parse(&content), 18));
+/// Solution of the twenty-oneth problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Rules;
+
+    fn day(&self) -> u32 {
+        21
+    }
+
+    fn title(&self) -> &'static str {
+        "Fractal Art"
+    }
+
+    fn parse(&self, content: &str) -> Result<Rules> {
+        parse(content)
+    }
+
+    fn part1(&self, input: &Rules) -> Result<Option<String>> {
+        Ok(Some(solve_generic(input.clone(), 5)?.to_string()))
+    }
+
+    fn part2(&self, input: &Rules) -> Result<Option<String>> {
+        Ok(Some(solve_generic(input.clone(), 18)?.to_string()))
+    }
 }
 
-fn vec_to_mat(vec: &Vec<&str>) -> Mat {
+fn vec_to_mat(vec: &Vec<&str>) -> Option<Mat> {
     let size = vec.len();
+    if vec.iter().any(|line| line.len() != size) { return None }
     let grid: Vec<usize> = vec.iter()
                            .map(|line| line.chars()
                                            .map(|c| match c {
@@ -47,7 +49,7 @@ fn vec_to_mat(vec: &Vec<&str>) -> Mat {
                                            .collect::<Vec<usize>>())
                            .flatten()
                            .collect();
-    Mat::from_shape_vec((size, size), grid).unwrap()
+    Mat::from_shape_vec((size, size), grid).ok()
 }
 
 fn flip(mat: &Mat) -> Mat {
@@ -82,30 +84,45 @@ fn rot(mat: &Mat, deg: usize ) -> Mat {
     }
 }
 
-fn parse(input: &str) -> HashMap<Mat, Mat> {
+/// Parse the input of the twenty-oneth problem.
+pub fn parse(input: &str) -> Result<Rules> {
     let mut rules = HashMap::new();
-    let parsed: Vec<(Mat, Mat)> = 
-        input.lines()
-            .map(|line| rule(line).to_result().unwrap())
-            .map(|(a, b)| (vec_to_mat(&a), vec_to_mat(&b)))
-            .collect();
+    let parsed: Vec<(Mat, Mat)> = parse_lines(input, |line| {
+        let (a, b) = rule(line).to_result()
+            .map_err(|_| Error::parse(column(line, line.trim()),
+                                      "Expected a rule `../.# => ##./#../...`"))?;
+        match (vec_to_mat(&a), vec_to_mat(&b)) {
+            (Some(ref a), Some(ref b)) if a.shape()[0] < 2 || a.shape()[0] > 3 || b.shape()[0] != a.shape()[0] + 1 => {
+                Err(Error::parse(column(line, line.trim()), "Expected a 2x2 pattern enhanced to 3x3, or 3x3 to 4x4"))
+            },
+            (Some(a), Some(b)) => Ok((a, b)),
+            _ => Err(Error::parse(column(line, line.trim()), "Patterns must be square")),
+        }
+    })?;
     for (f, t) in parsed {
         for deg in [0, 90, 180, 270].iter() {
             rules.insert(rot(&f, *deg), rot(&t, 0));
             rules.insert(rot(&flip(&f), *deg), rot(&t, 0));
         }
     }
-    rules
+    Ok(rules)
+}
+
+/// Pattern of a square, written as in the rules.
+fn pattern(mat: &Mat) -> String {
+    mat.genrows()
+       .into_iter()
+       .map(|row| row.iter().map(|&v| if v == 1 { '#' } else { '.' }).collect::<String>())
+       .join("/")
 }
 
-fn solve_generic(val: Rules, cycles: usize) -> usize {
+/// Solve the twenty-oneth problem with the given number of iterations.
+pub fn solve_generic(val: Rules, cycles: usize) -> Result<usize> {
     let mut grid = arr2(&[[0, 1, 0], [0, 0, 1], [1, 1, 1]]);
-    for c in 0..cycles {
+    for _ in 0..cycles {
         let size = grid.shape()[0] as usize;
         let mut steps: usize;
         let mut stepsize: usize;
-        // println!("<{}:{}>", steps, stepsize);
-
         if size % 2 == 0 {
             stepsize = 2;
             steps = size / 2;
@@ -118,45 +135,51 @@ fn solve_generic(val: Rules, cycles: usize) -> usize {
 
         let newsize = steps as usize * (stepsize + 1) as usize;
         let mut newgrid = Mat::zeros((newsize, newsize));
-        println!("CYCLE: {} | OUTPUT SIZE: {}", c, newsize);
 
         for x in 0..steps as usize {
             for y in 0..steps as usize {
-                let clone = grid.clone();
                 let x_from = (x * stepsize) as isize;
                 let y_from = (y * stepsize) as isize;
                 let x_to = x_from + stepsize as isize;
                 let y_to = y_from + stepsize as isize;
-                let sub = clone.slice(s![x_from..x_to, y_from..y_to]);
-                // println!("{:?}", &sub);
-                let new = val.get(&sub.to_owned()).unwrap();
-                // println!("{:?}", &new);
+                let sub = grid.slice(s![x_from..x_to, y_from..y_to]).to_owned();
+                let new = val.get(&sub)
+                             .ok_or_else(|| Error::NoSolution(format!("no rule for the pattern {}", pattern(&sub))))?;
                 let size = new.shape()[0];
                 for i in 0..size {
                     for j in 0..size {
                         newgrid[[x*size + i, y*size + j]] = new[[i, j]];
                     }
                 }
-                // println!("{:?}\n", &new);
             }
         }
 
         grid = newgrid;
-        // println!("{:?}\n---------------\n\n", &grid);
     }
-    grid.scalar_sum()
+    Ok(grid.scalar_sum())
 }
 
 #[cfg(test)]
 mod tests {
 
+    use error::Error;
     use super::{parse, solve_generic};
 
     const INPUT: &str = "../.# => ##./#../...\n.#./..#/### => #..#/..../..../#..#\n";
 
     #[test]
     fn twentyoneth_problem() {
-        assert_eq!(solve_generic(parse(INPUT), 2), 12);
+        assert_eq!(solve_generic(parse(INPUT).unwrap(), 2).unwrap(), 12);
+    }
+
+    #[test]
+    fn twentyoneth_problem_missing_rule() {
+        let rules = parse("../.. => ###/###/###").unwrap();
+        match solve_generic(rules, 1) {
+            Err(Error::NoSolution(message)) => assert_eq!(message, "no rule for the pattern .#./..#/###"),
+            other => panic!("Expected a missing rule, got {:?}", other),
+        }
+        assert!(parse("../.. => ##/##").is_err());
     }
 
 }
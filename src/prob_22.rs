@@ -1,9 +1,8 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashSet;
+use error::Result;
+use solution::Solution;
 
-type Grid = HashSet<(isize, isize)>;
+pub type Grid = HashSet<(isize, isize)>;
 
 enum Direction {
     Up,
@@ -39,31 +38,35 @@ impl Direction {
     }
 }
 
-/// Solve the twenty-second problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/22.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 22 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 22 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the first part
-    println!("22. Solutions to the twenty-second problem:");
-    println!("\tFirst part: {}", solve_first_part(parse(&content), 10_000));
+/// Solution of the twenty-second problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Grid;
+
+    fn day(&self) -> u32 {
+        22
+    }
+
+    fn title(&self) -> &'static str {
+        "Sporifica Virus"
+    }
+
+    fn parse(&self, content: &str) -> Result<Grid> {
+        Ok(parse(content))
+    }
+
+    fn part1(&self, input: &Grid) -> Result<Option<String>> {
+        Ok(Some(solve_first_part(input.clone(), 10_000).to_string()))
+    }
+
+    fn part2(&self, _input: &Grid) -> Result<Option<String>> {
+        Ok(None)
+    }
 }
 
-fn parse(input: &str) -> Grid {
+/// Parse the input of the twenty-second problem.
+pub fn parse(input: &str) -> Grid {
     let mut grid: Grid = HashSet::new();
     let center: Vec<Vec<bool>> = 
         input.lines()
@@ -82,7 +85,8 @@ fn parse(input: &str) -> Grid {
     grid
 }
 
-fn solve_first_part(mut grid: Grid, cycles: usize) -> usize {
+/// Solve the first part of the twenty-second problem.
+pub fn solve_first_part(mut grid: Grid, cycles: usize) -> usize {
     let mut x: isize = 0;
     let mut y: isize = 0;
     let mut dir: Direction = Direction::Up;
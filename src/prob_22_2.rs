@@ -1,12 +1,11 @@
-use std::fs::File;
-use std::io::Read;
-use std::process;
 use std::collections::HashMap;
+use error::Result;
+use solution::Solution;
 
-type Grid = HashMap<(isize, isize), State>;
+pub type Grid = HashMap<(isize, isize), State>;
 
-#[derive(Debug)]
-enum State {
+#[derive(Debug, Clone)]
+pub enum State {
     Weakened,
     Infected,
     Flagged,
@@ -46,30 +45,35 @@ impl Direction {
     }
 }
 
-/// Solve the twenty-second problem.
-pub fn solve() {
-    // Open the input file of the problem
-    let mut file = match File::open("input/22.txt") {
-        Ok(file) => file,
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 22 not found");
-            process::exit(1)
-        }
-    };
-    // Read the file in a String variable
-    let mut content: String = String::new();
-    match file.read_to_string(&mut content) {
-        Ok(_) => (),
-        Err(_) => {
-            eprintln!("Error: Input file for the problem 22 can't be read");
-            process::exit(1)
-        }
-    };
-    // Compute and print the solutions of the first part
-    println!("\tSecond part: {}", solve_second_part(parse(&content), 10000000));
+/// Solution of the twenty-second problem.
+pub struct Problem;
+
+impl Solution for Problem {
+    type Input = Grid;
+
+    fn day(&self) -> u32 {
+        22
+    }
+
+    fn title(&self) -> &'static str {
+        "Sporifica Virus"
+    }
+
+    fn parse(&self, content: &str) -> Result<Grid> {
+        Ok(parse(content))
+    }
+
+    fn part1(&self, _input: &Grid) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    fn part2(&self, input: &Grid) -> Result<Option<String>> {
+        Ok(Some(solve_second_part(input.clone(), 10_000_000).to_string()))
+    }
 }
 
-fn parse(input: &str) -> Grid {
+/// Parse the input of the twenty-second problem.
+pub fn parse(input: &str) -> Grid {
     let mut grid: Grid = HashMap::new();
     let center: Vec<Vec<bool>> = 
         input.lines()
@@ -88,7 +92,8 @@ fn parse(input: &str) -> Grid {
     grid
 }
 
-fn solve_second_part(mut grid: Grid, cycles: usize) -> usize {
+/// Solve the second part of the twenty-second problem.
+pub fn solve_second_part(mut grid: Grid, cycles: usize) -> usize {
     let mut x: isize = 0;
     let mut y: isize = 0;
     let mut dir: Direction = Direction::Up;
@@ -0,0 +1,198 @@
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use solution::{Answer, Solver, solve};
+
+/// Output formats of the answers.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Human readable text.
+    Text,
+    /// JSON array of records.
+    Json,
+    /// CSV table of records, with a header line.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Format, String> {
+        match value {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            other => Err(format!("Unknown format `{}`", other)),
+        }
+    }
+}
+
+/// Result of a part of a problem, or failure of the whole problem.
+#[derive(Debug, Clone)]
+pub struct Record {
+    pub day: u32,
+    pub title: &'static str,
+    /// Hash of the input, `None` if it can't be read.
+    pub input_hash: Option<String>,
+    /// Answer of the part, or message of the error that prevented solving it.
+    pub outcome: Result<Answer, String>,
+}
+
+impl Record {
+    /// Record of a problem that failed to be solved.
+    pub fn failure(solver: &Solver, input_hash: Option<String>, message: String) -> Record {
+        Record {
+            day: solver.day(),
+            title: solver.title(),
+            input_hash,
+            outcome: Err(message),
+        }
+    }
+}
+
+/// Solve the problem and return one record per part solved, or a single
+/// failure record.
+pub fn records(solver: &Solver, content: &str) -> Vec<Record> {
+    let hash = input_hash(content);
+    match solve(solver, content) {
+        Ok(answers) => answers.into_iter()
+                              .map(|answer| Record {
+                                  day: solver.day(),
+                                  title: solver.title(),
+                                  input_hash: Some(hash.clone()),
+                                  outcome: Ok(answer),
+                              })
+                              .collect(),
+        Err(err) => vec![Record::failure(solver, Some(hash), err.to_string())],
+    }
+}
+
+/// Hash identifying an input (64-bit FNV-1a, stable across builds and runs).
+pub fn input_hash(content: &str) -> String {
+    let hash = content.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Duration in milliseconds, with a microsecond precision.
+pub fn millis(duration: Duration) -> String {
+    let micros = duration.as_secs() * 1_000_000 + u64::from(duration.subsec_nanos()) / 1_000;
+    format!("{}.{:03}", micros / 1_000, micros % 1_000)
+}
+
+/// Render the records as a JSON array, one record per line.
+pub fn json(records: &[Record]) -> String {
+    let mut out = String::from("[\n");
+    for (i, record) in records.iter().enumerate() {
+        let (part, answer, elapsed, error) = match record.outcome {
+            Ok(ref answer) => (answer.part.to_string(),
+                               json_string(&answer.value),
+                               millis(answer.elapsed),
+                               String::from("null")),
+            Err(ref message) => (String::from("null"),
+                                 String::from("null"),
+                                 String::from("null"),
+                                 json_string(message)),
+        };
+        let hash = record.input_hash.as_ref().map_or(String::from("null"), |h| json_string(h));
+        write!(out,
+               "  {{\"day\": {}, \"part\": {}, \"answer\": {}, \"elapsed_ms\": {}, \
+                \"input_hash\": {}, \"error\": {}}}",
+               record.day, part, answer, elapsed, hash, error).unwrap();
+        out.push_str(if i + 1 < records.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Render the records as a CSV table, empty fields standing for missing values.
+pub fn csv(records: &[Record]) -> String {
+    let mut out = String::from("day,part,answer,elapsed_ms,input_hash,error\n");
+    for record in records {
+        let (part, answer, elapsed, error) = match record.outcome {
+            Ok(ref answer) => (answer.part.to_string(),
+                               csv_field(&answer.value),
+                               millis(answer.elapsed),
+                               String::new()),
+            Err(ref message) => (String::new(), String::new(), String::new(), csv_field(message)),
+        };
+        let hash = record.input_hash.clone().unwrap_or_default();
+        writeln!(out, "{},{},{},{},{},{}", record.day, part, answer, elapsed, hash, error).unwrap();
+    }
+    out
+}
+
+/// Quote and escape a string as a JSON string.
+fn json_string(value: &str) -> String {
+    let mut out = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Quote a CSV field if it contains a separator, a quote or a line break.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        String::from(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::time::Duration;
+
+    use solution::Answer;
+    use super::{Record, csv, input_hash, json};
+
+    fn sample() -> Vec<Record> {
+        vec![
+            Record {
+                day: 7,
+                title: "Recursive Circus",
+                input_hash: Some(input_hash("")),
+                outcome: Ok(Answer { part: 1, value: String::from("tknk"), elapsed: Duration::new(1, 2_500) }),
+            },
+            Record {
+                day: 8,
+                title: "I Heard You Like Registers",
+                input_hash: None,
+                outcome: Err(String::from("Wrong \"inx\", expected inc")),
+            },
+        ]
+    }
+
+    #[test]
+    fn json_records() {
+        assert_eq!(json(&sample()),
+                   "[\n\
+                    \x20 {\"day\": 7, \"part\": 1, \"answer\": \"tknk\", \"elapsed_ms\": 1000.002, \
+                    \"input_hash\": \"cbf29ce484222325\", \"error\": null},\n\
+                    \x20 {\"day\": 8, \"part\": null, \"answer\": null, \"elapsed_ms\": null, \
+                    \"input_hash\": null, \"error\": \"Wrong \\\"inx\\\", expected inc\"}\n\
+                    ]\n");
+    }
+
+    #[test]
+    fn csv_records() {
+        assert_eq!(csv(&sample()),
+                   "day,part,answer,elapsed_ms,input_hash,error\n\
+                    7,1,tknk,1000.002,cbf29ce484222325,\n\
+                    8,,,,,\"Wrong \"\"inx\"\", expected inc\"\n");
+    }
+
+}
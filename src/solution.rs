@@ -0,0 +1,192 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::{Duration, Instant};
+
+use error::{Error, Result};
+
+use prob_01;
+use prob_02;
+use prob_03;
+use prob_04;
+use prob_05;
+use prob_06;
+use prob_07;
+use prob_08;
+use prob_09;
+use prob_10;
+use prob_11;
+use prob_12;
+use prob_13;
+use prob_14;
+use prob_15;
+use prob_16;
+use prob_17;
+use prob_18;
+use prob_18_2;
+use prob_19;
+use prob_20;
+use prob_21;
+use prob_22;
+use prob_22_2;
+use prob_23;
+
+/// Answer to one of the parts of a problem.
+#[derive(Debug, Clone)]
+pub struct Answer {
+    /// Part of the problem, 1 or 2.
+    pub part: u32,
+    /// Value of the answer.
+    pub value: String,
+    /// Time spent computing the answer, parsing excluded.
+    pub elapsed: Duration,
+}
+
+/// Common interface of the solutions to the problems.
+///
+/// A solution parses the content of its input once, then computes the
+/// answers of the two parts from the parsed input. A part that is not
+/// solved by the implementation returns `None`. Malformed inputs and inputs
+/// without solution are reported as errors.
+pub trait Solution {
+    /// Parsed representation of the input of the problem.
+    type Input: 'static;
+
+    /// Day of the calendar of the problem.
+    fn day(&self) -> u32;
+
+    /// Title of the problem.
+    fn title(&self) -> &'static str;
+
+    /// Parse the content of the input.
+    fn parse(&self, content: &str) -> Result<Self::Input>;
+
+    /// Solve the first part of the problem.
+    fn part1(&self, input: &Self::Input) -> Result<Option<String>>;
+
+    /// Solve the second part of the problem.
+    fn part2(&self, input: &Self::Input) -> Result<Option<String>>;
+}
+
+/// Type-erased version of `Solution`, so that solutions with different inputs
+/// can be stored together in the registry.
+pub trait Solver {
+    fn day(&self) -> u32;
+    fn title(&self) -> &'static str;
+    fn parse(&self, content: &str) -> Result<Box<Any>>;
+    fn part1(&self, input: &Any) -> Result<Option<String>>;
+    fn part2(&self, input: &Any) -> Result<Option<String>>;
+}
+
+impl<S: Solution> Solver for S {
+    fn day(&self) -> u32 {
+        Solution::day(self)
+    }
+
+    fn title(&self) -> &'static str {
+        Solution::title(self)
+    }
+
+    fn parse(&self, content: &str) -> Result<Box<Any>> {
+        let input = Solution::parse(self, content)?;
+        Ok(Box::new(input))
+    }
+
+    fn part1(&self, input: &Any) -> Result<Option<String>> {
+        Solution::part1(self, downcast::<S>(input))
+    }
+
+    fn part2(&self, input: &Any) -> Result<Option<String>> {
+        Solution::part2(self, downcast::<S>(input))
+    }
+}
+
+fn downcast<S: Solution>(input: &Any) -> &S::Input {
+    match input.downcast_ref::<S::Input>() {
+        Some(input) => input,
+        None => panic!("Input not parsed by the same solution"),
+    }
+}
+
+/// Registry of all the available solutions, ordered by day.
+///
+/// Several solutions can be registered for the same day, for instance when the
+/// two parts are solved by different implementations.
+pub fn registry() -> Vec<Box<Solver>> {
+    vec![
+        Box::new(prob_01::Problem),
+        Box::new(prob_02::Problem),
+        Box::new(prob_03::Problem),
+        Box::new(prob_04::Problem),
+        Box::new(prob_05::Problem),
+        Box::new(prob_06::Problem),
+        Box::new(prob_07::Problem),
+        Box::new(prob_08::Problem),
+        Box::new(prob_09::Problem),
+        Box::new(prob_10::Problem),
+        Box::new(prob_11::Problem),
+        Box::new(prob_12::Problem),
+        Box::new(prob_13::Problem),
+        Box::new(prob_14::Problem),
+        Box::new(prob_15::Problem),
+        Box::new(prob_16::Problem),
+        Box::new(prob_17::Problem),
+        Box::new(prob_18::Problem),
+        Box::new(prob_18_2::Problem),
+        Box::new(prob_19::Problem),
+        Box::new(prob_20::Problem),
+        Box::new(prob_21::Problem),
+        Box::new(prob_22::Problem),
+        Box::new(prob_22_2::Problem),
+        Box::new(prob_23::Problem),
+    ]
+}
+
+/// Compute the answers of the parts solved by the solver, with their timings.
+///
+/// A solver panicking is reported as an error, so that the other problems can
+/// still be solved.
+pub fn solve(solver: &Solver, content: &str) -> Result<Vec<Answer>> {
+    guarded(|| {
+        let input = solver.parse(content)?;
+        let mut answers = Vec::new();
+        if let Some(answer) = timed(1, || solver.part1(&*input))? {
+            answers.push(answer);
+        }
+        if let Some(answer) = timed(2, || solver.part2(&*input))? {
+            answers.push(answer);
+        }
+        Ok(answers)
+    })
+}
+
+/// Run the function, reporting a panic as an error.
+///
+/// The panic hook is silenced meanwhile, the error being the only diagnostic.
+pub(crate) fn guarded<T, F>(func: F) -> Result<T>
+    where F: FnOnce() -> Result<T>
+{
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| ()));
+    let result = panic::catch_unwind(AssertUnwindSafe(func));
+    panic::set_hook(hook);
+    match result {
+        Ok(result) => result,
+        Err(cause) => match cause.downcast_ref::<&str>() {
+            Some(msg) => Err(Error::Panic(msg.to_string())),
+            None => match cause.downcast_ref::<String>() {
+                Some(msg) => Err(Error::Panic(msg.clone())),
+                None => Err(Error::Panic(String::from("unknown cause"))),
+            },
+        },
+    }
+}
+
+/// Run the solver of a part, measuring the time it takes.
+fn timed<F>(part: u32, func: F) -> Result<Option<Answer>>
+    where F: FnOnce() -> Result<Option<String>>
+{
+    let start = Instant::now();
+    let value = func()?;
+    let elapsed = start.elapsed();
+    Ok(value.map(|value| Answer { part, value, elapsed }))
+}
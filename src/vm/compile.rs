@@ -0,0 +1,378 @@
+//! Compilation of the programs to a flat bytecode, whose registers are
+//! resolved to slots of an array and whose jumps are resolved to positions.
+//!
+//! The compiled machine has the same semantics as `Proc`, but is much faster
+//! on long-running programs. `Proc` is still the one to use for debugging.
+
+use super::{Instruction, Io, Opcode, Program, Receive, Registers, State, Val, NB_OPCODES};
+use super::optimize::Native;
+
+/// Operand resolved to a constant or the slot of a register.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Arg {
+    Const(isize),
+    Slot(usize),
+}
+
+/// Destination of a jump.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Jump {
+    /// Position of the next instruction.
+    To(usize),
+    /// Out of the program, halting it.
+    Out,
+    /// Offset held by a register, resolved when jumping.
+    By(usize),
+}
+
+/// Operations of the bytecode.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Op {
+    Snd(Arg),
+    Rcv(Arg),
+    Set(usize, Arg),
+    Add(usize, Arg),
+    Sub(usize, Arg),
+    Mul(usize, Arg),
+    Mod(usize, Arg),
+    Jgz(Arg, Jump),
+    Jnz(Arg, Jump),
+    /// Instruction without effect, writing to a constant.
+    Nop,
+    /// Native operation, along with the operation to execute when it doesn't
+    /// apply.
+    Native(Native, usize),
+}
+
+/// Compiled program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Code {
+    pub ops: Vec<Op>,
+    /// Opcodes of the source instructions, to count the executed ones.
+    pub opcodes: Vec<Opcode>,
+    /// Names of the registers, by slot.
+    pub registers: Vec<char>,
+    /// Operations executed in place of the native ones when they don't apply,
+    /// with the opcodes of their source instructions.
+    fallbacks: Vec<(Op, Opcode)>,
+}
+
+impl Code {
+    /// Slot of the register, `None` if the program doesn't use it.
+    pub fn slot(&self, reg: char) -> Option<usize> {
+        self.registers.iter().position(|&r| r == reg)
+    }
+
+    fn resolve(&mut self, val: Val) -> Arg {
+        match val {
+            Val::Int(v) => Arg::Const(v),
+            Val::Reg(reg) => Arg::Slot(self.slot_of(reg)),
+        }
+    }
+
+    fn slot_of(&mut self, reg: char) -> usize {
+        match self.slot(reg) {
+            Some(slot) => slot,
+            None => {
+                self.registers.push(reg);
+                self.registers.len() - 1
+            },
+        }
+    }
+
+    fn jump(&mut self, pc: usize, len: usize, offset: Val) -> Jump {
+        match offset {
+            Val::Int(offset) => {
+                let target = pc as isize + offset;
+                if target < 0 || target >= len as isize { Jump::Out } else { Jump::To(target as usize) }
+            },
+            Val::Reg(reg) => Jump::By(self.slot_of(reg)),
+        }
+    }
+
+    /// Lower an instruction at the given position of a program of the given
+    /// length.
+    fn lower(&mut self, instruction: &Instruction, pc: usize, len: usize) -> Op {
+        let binary = |code: &mut Code, a: Val, b: Val, op: fn(usize, Arg) -> Op| match a {
+            Val::Reg(reg) => {
+                let slot = code.slot_of(reg);
+                op(slot, code.resolve(b))
+            },
+            Val::Int(_) => {
+                code.resolve(b);
+                Op::Nop
+            },
+        };
+        match *instruction {
+            Instruction::Snd(a) => Op::Snd(self.resolve(a)),
+            Instruction::Rcv(a) => Op::Rcv(self.resolve(a)),
+            Instruction::Set(a, b) => binary(self, a, b, Op::Set),
+            Instruction::Add(a, b) => binary(self, a, b, Op::Add),
+            Instruction::Sub(a, b) => binary(self, a, b, Op::Sub),
+            Instruction::Mul(a, b) => binary(self, a, b, Op::Mul),
+            Instruction::Mod(a, b) => binary(self, a, b, Op::Mod),
+            Instruction::Jgz(a, b) => {
+                let cond = self.resolve(a);
+                Op::Jgz(cond, self.jump(pc, len, b))
+            },
+            Instruction::Jnz(a, b) => {
+                let cond = self.resolve(a);
+                Op::Jnz(cond, self.jump(pc, len, b))
+            },
+            Instruction::Native(native) => {
+                let fallback = self.lower(&native.head(), pc, len);
+                self.fallbacks.push((fallback, native.head().opcode()));
+                Op::Native(native, self.fallbacks.len() - 1)
+            },
+        }
+    }
+}
+
+/// Compile the program to bytecode.
+pub fn compile(program: &Program) -> Code {
+    let mut code = Code {
+        ops: Vec::with_capacity(program.len()),
+        opcodes: program.iter().map(|instruction| instruction.opcode()).collect(),
+        registers: Vec::new(),
+        fallbacks: Vec::new(),
+    };
+    for (pc, instruction) in program.iter().enumerate() {
+        let op = code.lower(instruction, pc, program.len());
+        code.ops.push(op);
+    }
+    code
+}
+
+/// Control flow after an operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Flow {
+    /// Go on at the given position.
+    Next(usize),
+    /// Stop after the operation.
+    Stop,
+    /// Jump out of the program.
+    Out,
+    /// Wait for a message, executing the operation again on the next step.
+    Block,
+    /// Execute the given fallback instead of the native operation.
+    Fallback(usize),
+    /// Stop on the operation, which can't be executed.
+    Fault,
+}
+
+/// Process executing a compiled program.
+#[derive(Debug, Clone)]
+pub struct Machine<'a, I: Io> {
+    code: &'a Code,
+    pub pc: usize,
+    pub io: I,
+    regs: Vec<isize>,
+    /// Number of executed instructions of each opcode.
+    counts: [usize; NB_OPCODES],
+    halted: bool,
+}
+
+impl<'a, I: Io> Machine<'a, I> {
+    pub fn new(code: &'a Code, io: I) -> Machine<'a, I> {
+        Machine {
+            code,
+            pc: 0,
+            io,
+            regs: vec![0; code.registers.len()],
+            counts: [0; NB_OPCODES],
+            halted: false,
+        }
+    }
+
+    /// Whether the process halted.
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Number of executed instructions with the given opcode.
+    pub fn count(&self, opcode: Opcode) -> usize {
+        self.counts[opcode as usize]
+    }
+
+    #[inline]
+    fn arg(&self, arg: Arg) -> isize {
+        match arg {
+            Arg::Const(v) => v,
+            Arg::Slot(slot) => self.regs[slot],
+        }
+    }
+
+    /// Position following a jump from the current instruction.
+    fn jump(&self, jump: Jump) -> Flow {
+        let target = match jump {
+            Jump::To(target) => return Flow::Next(target),
+            Jump::Out => return Flow::Out,
+            Jump::By(slot) => self.pc as isize + self.regs[slot],
+        };
+        if target < 0 || target >= self.code.ops.len() as isize { Flow::Out } else { Flow::Next(target as usize) }
+    }
+
+    /// Execute an operation at the current position.
+    fn execute(&mut self, op: Op) -> Flow {
+        match op {
+            Op::Snd(a) => {
+                let value = self.arg(a);
+                self.io.send(value);
+            },
+            Op::Rcv(a) => {
+                let current = self.arg(a);
+                match self.io.receive(current) {
+                    Receive::Value(value) => if let Arg::Slot(slot) = a { self.regs[slot] = value },
+                    Receive::Skip => (),
+                    Receive::Block => return Flow::Block,
+                    Receive::Stop => return Flow::Stop,
+                }
+            },
+            Op::Set(a, b) => self.regs[a] = self.arg(b),
+            Op::Add(a, b) => self.regs[a] += self.arg(b),
+            Op::Sub(a, b) => self.regs[a] -= self.arg(b),
+            Op::Mul(a, b) => self.regs[a] *= self.arg(b),
+            Op::Mod(a, b) => match self.regs[a].checked_rem(self.arg(b)) {
+                Some(value) => self.regs[a] = value,
+                None => return Flow::Fault,
+            },
+            Op::Jgz(a, jump) if self.arg(a) > 0 => return self.jump(jump),
+            Op::Jnz(a, jump) if self.arg(a) != 0 => return self.jump(jump),
+            Op::Jgz(..) | Op::Jnz(..) | Op::Nop => (),
+            Op::Native(native, fallback) => {
+                if !native.apply(self) {
+                    return Flow::Fallback(fallback);
+                }
+                let target = self.pc + native.size();
+                return if target < self.code.ops.len() { Flow::Next(target) } else { Flow::Out };
+            },
+        }
+        Flow::Next(self.pc + 1)
+    }
+
+    /// Execute the current operation.
+    pub fn step(&mut self) -> State {
+        if self.halted || self.pc >= self.code.ops.len() {
+            self.halted = true;
+            return State::Halted;
+        }
+        let op = self.code.ops[self.pc];
+        let mut flow = self.execute(op);
+        let mut opcode = self.code.opcodes[self.pc];
+        // Count the fallback as the replaced instruction, as `Proc` does
+        if let Flow::Fallback(fallback) = flow {
+            let (op, fallback_opcode) = self.code.fallbacks[fallback];
+            flow = self.execute(op);
+            opcode = fallback_opcode;
+        }
+        match flow {
+            Flow::Block => return State::Blocked,
+            Flow::Fault => {
+                self.halted = true;
+                return State::Fault;
+            },
+            _ => (),
+        }
+        self.counts[opcode as usize] += 1;
+        match flow {
+            Flow::Next(pc) => {
+                self.pc = pc;
+                return State::Running;
+            },
+            Flow::Stop => self.pc += 1,
+            Flow::Out | Flow::Block | Flow::Fallback(_) | Flow::Fault => (),
+        }
+        self.halted = true;
+        State::Halted
+    }
+
+    /// Run the program until the process halts or blocks.
+    pub fn run(&mut self) -> State {
+        loop {
+            match self.step() {
+                State::Running => (),
+                state => return state,
+            }
+        }
+    }
+}
+
+impl<'a, I: Io> Registers for Machine<'a, I> {
+    fn get(&self, reg: char) -> isize {
+        self.code.slot(reg).map_or(0, |slot| self.regs[slot])
+    }
+
+    /// Set a register, ignoring the ones not used by the program.
+    fn set(&mut self, reg: char, value: isize) {
+        if let Some(slot) = self.code.slot(reg) {
+            self.regs[slot] = value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use vm::{Opcode, Proc, Queue, Registers, Sound, State, parse};
+    use vm::optimize::optimize;
+    use super::{Machine, compile};
+
+    const PROGRAM: &str = "set a 1\nadd a 2\nmul a a\nmod a 5\nsnd a\nset a 0\nrcv a\njgz a -1\n\
+                           set a 1\njgz a -2\nset b 7\nset c 3\nadd d b\nsub c 1\njnz c -2\n\
+                           set p 1\njgz p p\nsnd d\nrcv e\nsnd e";
+
+    #[test]
+    fn same_as_interpreter() {
+        let program = parse(PROGRAM).unwrap();
+        for program in &[program.clone(), optimize(&program)] {
+            let code = compile(program);
+            let mut machine = Machine::new(&code, Queue::default());
+            let mut vproc = Proc::new(Queue::default());
+            machine.io.inbox.push_back(5);
+            vproc.io.inbox.push_back(5);
+            assert_eq!(machine.run(), vproc.run(program));
+            assert_eq!(machine.run(), State::Blocked);
+            machine.io.inbox.push_back(11);
+            vproc.io.inbox.push_back(11);
+            assert_eq!(machine.run(), vproc.run(program));
+            for reg in "abcdep".chars() {
+                assert_eq!(machine.get(reg), vproc.register(reg));
+            }
+            assert_eq!(machine.io.outbox, vproc.io.outbox);
+            assert_eq!((machine.pc, machine.count(Opcode::Mul)), (vproc.pc, vproc.count(Opcode::Mul)));
+        }
+        // The loop adding b to d is entered with c at 0, so the native
+        // operation replacing it never applies and falls back to the `add`,
+        // the loop then running (almost) forever
+        let program = optimize(&parse("set b 7\nadd d b\nsub c 1\njnz c -2").unwrap());
+        assert_eq!(program[1].opcode(), Opcode::Native);
+        let code = compile(&program);
+        let mut machine = Machine::new(&code, Queue::default());
+        let mut vproc = Proc::new(Queue::default());
+        for _ in 0..100 {
+            assert_eq!(machine.step(), vproc.step(&program));
+        }
+        assert_eq!((machine.pc, machine.get('c'), machine.get('d')), (vproc.pc, vproc.register('c'), vproc.register('d')));
+        for &opcode in &[Opcode::Set, Opcode::Add, Opcode::Sub, Opcode::Jnz, Opcode::Native] {
+            assert_eq!(machine.count(opcode), vproc.count(opcode));
+        }
+        assert_eq!((machine.count(Opcode::Add), machine.count(Opcode::Native)), (33, 0));
+    }
+
+    #[test]
+    fn sound_mode() {
+        let code = compile(&parse(PROGRAM).unwrap());
+        let mut machine = Machine::new(&code, Sound::default());
+        assert_eq!(machine.run(), State::Halted);
+        assert_eq!(machine.io.last, Some(4));
+    }
+
+    #[test]
+    fn modulo_by_zero() {
+        let code = compile(&parse("set a 5\nmod a b\nsnd a").unwrap());
+        let mut machine = Machine::new(&code, Sound::default());
+        assert_eq!(machine.run(), State::Fault);
+        assert_eq!((machine.pc, machine.get('a'), machine.halted()), (1, 5, true));
+    }
+
+}
@@ -0,0 +1,296 @@
+//! Step debugger and tracer of the processes of the virtual machine.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use error::{Error, Result, column, next_token, parse_token};
+use super::{Io, Proc, Program, State, fault};
+
+/// Reason for the debugger to give the control back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stop {
+    /// The requested number of steps has been executed.
+    Steps,
+    /// The next instruction is on a breakpoint.
+    Breakpoint(usize),
+    /// A watched register changed, from the first value to the second one.
+    Watch(char, isize, isize),
+    /// The process waits for a message.
+    Blocked,
+    /// The process halted.
+    Halted,
+}
+
+/// Debugger controlling the execution of a process.
+pub struct Debugger<'a, I: Io> {
+    program: &'a Program,
+    pub vproc: Proc<I>,
+    breakpoints: BTreeSet<usize>,
+    /// Watched registers, with their last seen values.
+    watches: BTreeMap<char, isize>,
+    /// Number of executions of each line of the program.
+    hits: Vec<usize>,
+    /// Number of executed steps.
+    steps: usize,
+    trace: Option<Box<Write>>,
+}
+
+impl<'a, I: Io> Debugger<'a, I> {
+    pub fn new(program: &'a Program, vproc: Proc<I>) -> Debugger<'a, I> {
+        Debugger {
+            program,
+            vproc,
+            breakpoints: BTreeSet::new(),
+            watches: BTreeMap::new(),
+            hits: vec![0; program.len()],
+            steps: 0,
+            trace: None,
+        }
+    }
+
+    /// Write a line per executed step to the given writer.
+    pub fn trace_to(&mut self, writer: Box<Write>) {
+        self.trace = Some(writer);
+    }
+
+    pub fn add_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: usize) {
+        self.breakpoints.remove(&pc);
+    }
+
+    /// Stop whenever the value of the register changes.
+    pub fn watch(&mut self, reg: char) {
+        let value = self.vproc.register(reg);
+        self.watches.insert(reg, value);
+    }
+
+    pub fn unwatch(&mut self, reg: char) {
+        self.watches.remove(&reg);
+    }
+
+    /// Number of executions of each line of the program.
+    pub fn hits(&self) -> &[usize] {
+        &self.hits
+    }
+
+    /// Number of executed steps.
+    pub fn steps(&self) -> usize {
+        self.steps
+    }
+
+    /// Write what is left of the trace, which may be buffered.
+    pub fn finish(&mut self) -> Result<()> {
+        match self.trace {
+            Some(ref mut trace) => trace.flush().map_err(|err| Error::Io(String::from("<trace>"), err)),
+            None => Ok(()),
+        }
+    }
+
+    /// Execute a single instruction.
+    pub fn step(&mut self) -> Result<Stop> {
+        let pc = self.vproc.pc;
+        if self.vproc.halted() || pc >= self.program.len() {
+            return Ok(Stop::Halted);
+        }
+        let state = self.vproc.step(self.program);
+        match state {
+            State::Blocked => return Ok(Stop::Blocked),
+            State::Fault => return Err(fault(pc)),
+            _ => (),
+        }
+        self.hits[pc] += 1;
+        self.steps += 1;
+        if let Some(ref mut trace) = self.trace {
+            writeln!(trace, "{:>10}  {:>3}  {:<12}  {}",
+                     self.steps, pc, self.program[pc].to_string(), registers(&self.vproc))
+                .map_err(|err| Error::Io(String::from("<trace>"), err))?;
+        }
+        if state == State::Halted {
+            return Ok(Stop::Halted);
+        }
+        for (&reg, last) in &mut self.watches {
+            let value = self.vproc.register(reg);
+            if value != *last {
+                let old = *last;
+                *last = value;
+                return Ok(Stop::Watch(reg, old, value));
+            }
+        }
+        Ok(Stop::Steps)
+    }
+
+    /// Execute instructions until reaching a breakpoint, a change of a watched
+    /// register, the end of the process, or the maximum number of steps.
+    pub fn resume(&mut self, max: Option<usize>) -> Result<Stop> {
+        let mut count = 0;
+        loop {
+            if max.map_or(false, |max| count >= max) {
+                return Ok(Stop::Steps);
+            }
+            match self.step()? {
+                Stop::Steps => count += 1,
+                stop => return Ok(stop),
+            }
+            if self.breakpoints.contains(&self.vproc.pc) {
+                return Ok(Stop::Breakpoint(self.vproc.pc));
+            }
+        }
+    }
+
+    /// Execute a debugger command, writing its output to the writer.
+    ///
+    /// Return `false` when the command asks to quit.
+    pub fn command<W: Write>(&mut self, line: &str, out: &mut W) -> Result<bool> {
+        let mut tokens = line.split_whitespace();
+        let name = match tokens.next() {
+            Some(name) => name,
+            None => return Ok(true),
+        };
+        let stop = match name {
+            "step" | "s" => {
+                let count = match tokens.next() {
+                    Some(token) => parse_token::<usize>(line, token)?,
+                    None => 1,
+                };
+                Some(self.resume(Some(count))?)
+            },
+            "continue" | "c" => Some(self.resume(None)?),
+            "break" | "b" => {
+                self.add_breakpoint(parse_token(line, next_token(line, &mut tokens)?)?);
+                None
+            },
+            "delete" | "d" => {
+                self.remove_breakpoint(parse_token(line, next_token(line, &mut tokens)?)?);
+                None
+            },
+            "watch" | "w" => {
+                self.watch(register(line, next_token(line, &mut tokens)?)?);
+                None
+            },
+            "unwatch" | "u" => {
+                self.unwatch(register(line, next_token(line, &mut tokens)?)?);
+                None
+            },
+            "regs" | "r" => {
+                writeln!(out, "pc={} {}", self.vproc.pc, registers(&self.vproc)).map_err(output)?;
+                None
+            },
+            "counts" => {
+                for (pc, (instruction, hits)) in self.program.iter().zip(&self.hits).enumerate() {
+                    writeln!(out, "{:>3}  {:<12}  {}", pc, instruction.to_string(), hits).map_err(output)?;
+                }
+                None
+            },
+            "quit" | "q" => return Ok(false),
+            _ => return Err(Error::parse(column(line, name), format!("Unknown command `{}`", name))),
+        };
+        match stop {
+            Some(Stop::Steps) | None => (),
+            Some(Stop::Breakpoint(pc)) => writeln!(out, "Breakpoint at {}", pc).map_err(output)?,
+            Some(Stop::Watch(reg, old, new)) => {
+                writeln!(out, "Register {} changed from {} to {}", reg, old, new).map_err(output)?
+            },
+            Some(Stop::Blocked) => writeln!(out, "Process blocked").map_err(output)?,
+            Some(Stop::Halted) => writeln!(out, "Process halted").map_err(output)?,
+        }
+        if stop.is_some() {
+            match self.program.get(self.vproc.pc) {
+                Some(instruction) => writeln!(out, "{:>3}  {}", self.vproc.pc, instruction),
+                None => writeln!(out, "{:>3}  <end>", self.vproc.pc),
+            }.map_err(output)?;
+        }
+        Ok(true)
+    }
+}
+
+/// Register named by a token of a command.
+fn register(line: &str, token: &str) -> Result<char> {
+    match token.chars().nth(0) {
+        Some(reg) if reg.is_alphabetic() && token.len() == 1 => Ok(reg),
+        _ => Err(Error::parse(column(line, token), format!("Invalid register `{}`", token))),
+    }
+}
+
+/// Registers of the process, sorted by name.
+fn registers<I: Io>(vproc: &Proc<I>) -> String {
+    let regs: BTreeMap<&char, &isize> = vproc.regs.iter().collect();
+    regs.into_iter()
+        .map(|(reg, value)| format!("{}={}", reg, value))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn output(err: ::std::io::Error) -> Error {
+    Error::Io(String::from("<output>"), err)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::cell::RefCell;
+    use std::io::{self, BufWriter, Write};
+    use std::rc::Rc;
+
+    use vm::{Proc, Sound, parse};
+    use super::{Debugger, Stop};
+
+    const PROGRAM: &str = "set a 3\nsub a 1\njnz a -1\nset b 1";
+
+    #[test]
+    fn breakpoints_and_watches() {
+        let program = parse(PROGRAM).unwrap();
+        let mut debugger = Debugger::new(&program, Proc::new(Sound::default()));
+        debugger.add_breakpoint(2);
+        assert_eq!(debugger.resume(None).unwrap(), Stop::Breakpoint(2));
+        debugger.remove_breakpoint(2);
+        debugger.watch('b');
+        assert_eq!(debugger.resume(None).unwrap(), Stop::Watch('b', 0, 1));
+        assert_eq!(debugger.resume(None).unwrap(), Stop::Halted);
+        assert_eq!(debugger.hits(), &[1, 3, 3, 1]);
+    }
+
+    #[test]
+    fn scripted_session() {
+        let program = parse(PROGRAM).unwrap();
+        let mut debugger = Debugger::new(&program, Proc::new(Sound::default()));
+        let mut out: Vec<u8> = Vec::new();
+        for line in &["step 2", "regs", "watch b", "c", "q"] {
+            debugger.command(line, &mut out).unwrap();
+        }
+        assert_eq!(String::from_utf8(out).unwrap(),
+                   "  2  jnz a -1\n\
+                    pc=2 a=2\n\
+                    Register b changed from 0 to 1\n\
+                    \x20 4  <end>\n");
+    }
+
+    /// Writer whose output is kept after being dropped.
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn buffered_trace() {
+        let program = parse(PROGRAM).unwrap();
+        let mut debugger = Debugger::new(&program, Proc::new(Sound::default()));
+        let written = Rc::new(RefCell::new(Vec::new()));
+        debugger.trace_to(Box::new(BufWriter::new(Shared(Rc::clone(&written)))));
+        debugger.resume(Some(2)).unwrap();
+        assert!(written.borrow().is_empty());
+        debugger.finish().unwrap();
+        assert_eq!(String::from_utf8(written.borrow().clone()).unwrap(),
+                   "         1    0  set a 3       a=3\n         2    1  sub a 1       a=2\n");
+    }
+
+}
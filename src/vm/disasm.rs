@@ -0,0 +1,336 @@
+//! Disassembly of the programs to annotated pseudo-code.
+//!
+//! Backward jumps enclosing a single-entry region are printed as loops, and
+//! forward conditional jumps over a single-entry region as conditionals. The
+//! remaining jumps are printed as `goto`s to labels named after the position
+//! of their target. Each line is annotated with the registers live after it.
+
+use std::collections::BTreeSet;
+
+use super::{Instruction, Program, Val};
+use super::optimize::{Cfg, Native, constant_jump, jump_target, successors};
+
+/// Column of the liveness comments.
+const COMMENT_COLUMN: usize = 36;
+
+/// Line of the pseudo-code.
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    /// Start of the code of the instruction at the given position, where its
+    /// label is printed if some `goto` jumps to it.
+    Mark { pc: usize, depth: usize },
+    /// Code, annotated with the liveness after the instruction it comes from.
+    Code { depth: usize, text: String, pc: Option<usize> },
+}
+
+/// Registers whose value is read by the instruction.
+fn uses(instruction: &Instruction) -> Vec<char> {
+    let operands = match *instruction {
+        Instruction::Set(_, b) => vec![b],
+        Instruction::Native(Native::MulAdd { dst, src, count }) => vec![Val::Reg(dst), src, Val::Reg(count)],
+        Instruction::Native(Native::Composite { n, flag, .. }) => vec![n, Val::Reg(flag)],
+        _ => instruction.operands(),
+    };
+    operands.into_iter()
+            .filter_map(|val| match val {
+                Val::Reg(reg) => Some(reg),
+                Val::Int(_) => None,
+            })
+            .collect()
+}
+
+/// Register always overwritten by the instruction.
+///
+/// `rcv` doesn't always write its register, and native operations may fall
+/// back to their first instruction, so they are considered as reading only.
+fn defines(instruction: &Instruction) -> Option<char> {
+    match *instruction {
+        Instruction::Set(Val::Reg(a), _) |
+        Instruction::Add(Val::Reg(a), _) |
+        Instruction::Sub(Val::Reg(a), _) |
+        Instruction::Mul(Val::Reg(a), _) |
+        Instruction::Mod(Val::Reg(a), _) => Some(a),
+        _ => None,
+    }
+}
+
+/// Positions of the instructions that can follow the one at the given
+/// position, the length of the program standing for its end.
+fn next(program: &Program, pc: usize) -> Vec<usize> {
+    match program[pc] {
+        Instruction::Jgz(_, Val::Reg(_)) |
+        Instruction::Jnz(_, Val::Reg(_)) if constant_jump(&program[pc]) != Some(false) => {
+            (0..program.len() + 1).collect()
+        },
+        Instruction::Native(native) => vec![pc + 1, (pc + native.size()).min(program.len())],
+        _ => successors(program, pc),
+    }
+}
+
+/// Registers live after each instruction, i.e. whose value may be read before
+/// being overwritten. No register is live at the end of the program.
+pub fn liveness(program: &Program) -> Vec<BTreeSet<char>> {
+    let len = program.len();
+    let next: Vec<Vec<usize>> = (0..len).map(|pc| next(program, pc)).collect();
+    let mut live_in: Vec<BTreeSet<char>> = vec![BTreeSet::new(); len + 1];
+    let mut live_out: Vec<BTreeSet<char>> = vec![BTreeSet::new(); len];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for pc in (0..len).rev() {
+            let out: BTreeSet<char> = next[pc].iter().flat_map(|&s| live_in[s].iter().cloned()).collect();
+            let mut inside = out.clone();
+            if let Some(reg) = defines(&program[pc]) {
+                inside.remove(&reg);
+            }
+            inside.extend(uses(&program[pc]));
+            if inside != live_in[pc] || out != live_out[pc] {
+                live_in[pc] = inside;
+                live_out[pc] = out;
+                changed = true;
+            }
+        }
+    }
+    live_out
+}
+
+/// Condition of a jump, negated or not.
+fn condition(instruction: &Instruction, negated: bool) -> String {
+    let (a, op) = match *instruction {
+        Instruction::Jgz(a, _) => (a, if negated { "<=" } else { ">" }),
+        Instruction::Jnz(a, _) => (a, if negated { "==" } else { "!=" }),
+        _ => unreachable!(),
+    };
+    format!("{} {} 0", a, op)
+}
+
+/// Statement of an instruction other than a jump.
+fn statement(instruction: &Instruction) -> String {
+    let update = |a: Val, op: &str, b: Val| match (a, op, b) {
+        (Val::Int(_), _, _) => format!("nop  ({})", instruction),
+        (_, "+=", Val::Int(v)) if v < 0 => format!("{} -= {}", a, -v),
+        (_, "-=", Val::Int(v)) if v < 0 => format!("{} += {}", a, -v),
+        _ => format!("{} {} {}", a, op, b),
+    };
+    match *instruction {
+        Instruction::Snd(a) => format!("snd({})", a),
+        Instruction::Rcv(a) => format!("rcv({})", a),
+        Instruction::Set(a, b) => update(a, "=", b),
+        Instruction::Add(a, b) => update(a, "+=", b),
+        Instruction::Sub(a, b) => update(a, "-=", b),
+        Instruction::Mul(a, b) => update(a, "*=", b),
+        Instruction::Mod(a, b) => update(a, "%=", b),
+        Instruction::Jgz(..) | Instruction::Jnz(..) => unreachable!(),
+        Instruction::Native(native) => native.to_string(),
+    }
+}
+
+/// Recovery of the structure of a program.
+struct Printer<'a> {
+    program: &'a Program,
+    cfg: Cfg,
+    lines: Vec<Line>,
+    /// Positions targeted by a `goto`.
+    labels: BTreeSet<usize>,
+}
+
+impl<'a> Printer<'a> {
+    fn code(&mut self, depth: usize, text: String, pc: Option<usize>) {
+        self.lines.push(Line::Code { depth, text, pc });
+    }
+
+    /// Constant target of the jump at the given position, only when it can be
+    /// taken.
+    fn target(&self, pc: usize) -> Option<usize> {
+        match (jump_target(self.program, pc), constant_jump(&self.program[pc])) {
+            (Some(target), None) | (Some(target), Some(true)) if target >= 0 => Some(target as usize),
+            _ => None,
+        }
+    }
+
+    /// Print the instructions of the range, `exit` being the position
+    /// following the innermost enclosing loop.
+    fn block(&mut self, start: usize, end: usize, depth: usize, exit: Option<usize>) {
+        let mut pc = start;
+        while pc < end {
+            self.lines.push(Line::Mark { pc, depth });
+            pc = match self.latch(pc, end) {
+                Some(latch) => self.looped(pc, latch, depth),
+                None => match self.branch(pc, end, depth, exit) {
+                    Some(next) => next,
+                    None => {
+                        self.jump_or_statement(pc, depth, exit);
+                        pc + 1
+                    },
+                },
+            };
+        }
+    }
+
+    /// Last instruction of the outermost loop starting at the position and
+    /// ending before the end of the range.
+    fn latch(&self, head: usize, end: usize) -> Option<usize> {
+        (head..end).rev().find(|&pc| self.target(pc) == Some(head) && self.cfg.single_entry(head, pc + 1))
+    }
+
+    fn looped(&mut self, head: usize, latch: usize, depth: usize) -> usize {
+        let conditional = constant_jump(&self.program[latch]).is_none();
+        self.code(depth, String::from(if conditional { "do {" } else { "loop {" }), None);
+        self.block(head, latch, depth + 1, Some(latch + 1));
+        let text = if conditional {
+            format!("}} while {}", condition(&self.program[latch], false))
+        } else {
+            String::from("}")
+        };
+        self.code(depth, text, Some(latch));
+        latch + 1
+    }
+
+    /// Print the conditional starting at the position, if any, returning the
+    /// position following it.
+    fn branch(&mut self, pc: usize, end: usize, depth: usize, exit: Option<usize>) -> Option<usize> {
+        if constant_jump(&self.program[pc]).is_some() {
+            return None;
+        }
+        let skip = match self.target(pc) {
+            Some(target) if pc + 1 < target && target <= end && self.cfg.single_entry(pc + 1, target) => target,
+            _ => return None,
+        };
+        let last = skip - 1;
+        let other = match (self.target(last), constant_jump(&self.program[last])) {
+            (Some(target), Some(true)) if skip < target && target <= end && self.cfg.single_entry(skip, target) => {
+                Some(target)
+            },
+            _ => None,
+        };
+        match other {
+            Some(after) if last == pc + 1 => {
+                self.code(depth, format!("if {} {{", condition(&self.program[pc], false)), Some(pc));
+                self.block(skip, after, depth + 1, exit);
+                self.code(depth, String::from("}"), None);
+                Some(after)
+            },
+            Some(after) => {
+                self.code(depth, format!("if {} {{", condition(&self.program[pc], true)), Some(pc));
+                self.block(pc + 1, last, depth + 1, exit);
+                self.code(depth, String::from("} else {"), Some(last));
+                self.block(skip, after, depth + 1, exit);
+                self.code(depth, String::from("}"), None);
+                Some(after)
+            },
+            None => {
+                self.code(depth, format!("if {} {{", condition(&self.program[pc], true)), Some(pc));
+                self.block(pc + 1, skip, depth + 1, exit);
+                self.code(depth, String::from("}"), None);
+                Some(skip)
+            },
+        }
+    }
+
+    fn jump_or_statement(&mut self, pc: usize, depth: usize, exit: Option<usize>) {
+        let instruction = self.program[pc].clone();
+        let goto = match instruction {
+            Instruction::Jgz(_, offset) | Instruction::Jnz(_, offset) => match (offset, self.target(pc)) {
+                _ if constant_jump(&instruction) == Some(false) => format!("nop  ({})", instruction),
+                (Val::Reg(reg), _) => format!("goto {} + {}", pc, reg),
+                (_, Some(target)) if Some(target) == exit => String::from("break"),
+                (_, Some(target)) if target < self.program.len() => {
+                    self.labels.insert(target);
+                    format!("goto L{}", target)
+                },
+                _ => String::from("halt"),
+            },
+            _ => return self.code(depth, statement(&instruction), Some(pc)),
+        };
+        let text = match constant_jump(&instruction) {
+            None => format!("if {} {{ {} }}", condition(&instruction, false), goto),
+            Some(_) => goto,
+        };
+        self.code(depth, text, Some(pc));
+    }
+}
+
+/// Disassemble the program to pseudo-code.
+pub fn disassemble(program: &Program) -> String {
+    let mut printer = Printer { program, cfg: Cfg::new(program), lines: Vec::new(), labels: BTreeSet::new() };
+    printer.block(0, program.len(), 0, None);
+    let live = liveness(program);
+    let mut output = String::new();
+    for line in printer.lines {
+        match line {
+            Line::Mark { pc, depth } => if printer.labels.remove(&pc) {
+                output.push_str(&format!("{}L{}:\n", "    ".repeat(depth), pc));
+            },
+            Line::Code { depth, text, pc } => {
+                let code = format!("{}{}", "    ".repeat(depth + 1), text);
+                match pc {
+                    Some(pc) => {
+                        let regs: Vec<String> = live[pc].iter().map(|reg| reg.to_string()).collect();
+                        output.push_str(&format!("{:<width$}  // live: {}\n",
+                                                 code,
+                                                 if regs.is_empty() { String::from("-") } else { regs.join(" ") },
+                                                 width = COMMENT_COLUMN));
+                    },
+                    None => output.push_str(&format!("{}\n", code)),
+                }
+            },
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+
+    use vm::parse;
+    use super::{disassemble, liveness};
+
+    #[test]
+    fn live_registers() {
+        let program = parse("set a 1\nset b 2\nadd a b\nsnd a\nset b 3").unwrap();
+        let live: Vec<String> = liveness(&program).into_iter().map(|regs| regs.into_iter().collect()).collect();
+        assert_eq!(live, vec!["a", "ab", "a", "", ""]);
+    }
+
+    #[test]
+    fn structured_pseudo_code() {
+        let program = parse("set b 5\njnz a 2\njnz 1 2\nmul b 2\nset c b\nsub c 1\njgz c -1\n\
+                             jnz b 2\nsnd b\njnz c -5").unwrap();
+        assert_eq!(disassemble(&program),
+                   "    b = 5                             // live: a b\n\
+                    \x20   if a != 0 {                       // live: b\n\
+                    \x20       b *= 2                        // live: b\n\
+                    \x20   }\n\
+                    \x20   do {\n\
+                    \x20       c = b                         // live: b c\n\
+                    \x20       do {\n\
+                    \x20           c -= 1                    // live: b c\n\
+                    \x20       } while c > 0                 // live: b c\n\
+                    \x20       if b == 0 {                   // live: b c\n\
+                    \x20           snd(b)                    // live: b c\n\
+                    \x20       }\n\
+                    \x20   } while c != 0                    // live: b\n");
+    }
+
+    #[test]
+    fn labels_and_gotos() {
+        let program = parse("set b 5\njnz a 3\nadd b 1\njnz 1 2\nsub b 1\njgz b 3\nsnd b\njnz 1 -6\n\
+                             rcv b\njgz b -2").unwrap();
+        assert_eq!(disassemble(&program),
+                   "    b = 5                             // live: a b\n\
+                    L1:\n\
+                    \x20   if a == 0 {                       // live: a b\n\
+                    \x20       b += 1                        // live: a b\n\
+                    \x20   } else {                          // live: a b\n\
+                    \x20       b -= 1                        // live: a b\n\
+                    \x20   }\n\
+                    \x20   if b > 0 { goto L8 }              // live: a b\n\
+                    \x20   snd(b)                            // live: a b\n\
+                    L7:\n\
+                    \x20   goto L1                           // live: a b\n\
+                    L8:\n\
+                    \x20   rcv(b)                            // live: a b\n\
+                    \x20   if b > 0 { goto L7 }              // live: a b\n");
+    }
+
+}
@@ -0,0 +1,482 @@
+//! Virtual machine running the assembly programs of the problems 18 and 23.
+//!
+//! The instruction set is the union of the ones of both problems. The
+//! semantics of `snd` and `rcv` depend on the `Io` of the process: playing
+//! sounds and recovering them, or sending and receiving messages.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use error::{Error, Result, column, next_token, parse_lines, parse_token};
+
+pub mod compile;
+pub mod debug;
+pub mod disasm;
+pub mod optimize;
+pub mod schedule;
+pub mod threaded;
+
+use self::optimize::Native;
+
+/// Instructions of a program.
+pub type Program = Vec<Instruction>;
+
+/// Operand of an instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Val {
+    Int(isize),
+    Reg(char),
+}
+
+impl Val {
+    pub fn new(value: &str) -> Result<Val> {
+        match value.chars().nth(0) {
+            Some(v) if v.is_digit(10) || v == '-' => Ok(Val::Int(parse_token(value, value)?)),
+            Some(v) if v.is_alphabetic() && value.len() == 1 => Ok(Val::Reg(v)),
+            Some(_) => Err(Error::parse(1, format!("Invalid register `{}`", value))),
+            None => Err(Error::parse(1, "Missing value")),
+        }
+    }
+}
+
+impl fmt::Display for Val {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Val::Int(v) => write!(f, "{}", v),
+            Val::Reg(reg) => write!(f, "{}", reg),
+        }
+    }
+}
+
+/// Parse the next operand of an instruction line.
+fn operand<'a, I>(line: &str, tokens: &mut I) -> Result<Val>
+    where I: Iterator<Item = &'a str>
+{
+    let token = next_token(line, tokens)?;
+    Val::new(token).map_err(|err| err.within(line, token))
+}
+
+/// Kinds of instructions, used to count the executed ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Snd,
+    Rcv,
+    Set,
+    Add,
+    Sub,
+    Mul,
+    Mod,
+    Jgz,
+    Jnz,
+    Native,
+}
+
+/// Number of different opcodes.
+pub const NB_OPCODES: usize = 10;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Snd(Val),
+    Rcv(Val),
+    Set(Val, Val),
+    Add(Val, Val),
+    Sub(Val, Val),
+    Mul(Val, Val),
+    Mod(Val, Val),
+    Jgz(Val, Val),
+    Jnz(Val, Val),
+    /// Native operation replacing a sequence of instructions starting with
+    /// this one, see the `optimize` module.
+    Native(Native),
+}
+
+impl Instruction {
+    pub fn new(value: &str) -> Result<Instruction> {
+        let mut tokens = value.split_whitespace();
+        match next_token(value, &mut tokens)? {
+            "snd" => Ok(Instruction::Snd(operand(value, &mut tokens)?)),
+            "rcv" => Ok(Instruction::Rcv(operand(value, &mut tokens)?)),
+            "set" => Ok(Instruction::Set(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "add" => Ok(Instruction::Add(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "sub" => Ok(Instruction::Sub(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "mul" => Ok(Instruction::Mul(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "mod" => Ok(Instruction::Mod(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "jgz" => Ok(Instruction::Jgz(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            "jnz" => Ok(Instruction::Jnz(operand(value, &mut tokens)?,
+                                         operand(value, &mut tokens)?)),
+            name => Err(Error::parse(column(value, name),
+                                     format!("Unknown instruction `{}`", name))),
+        }
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        match *self {
+            Instruction::Snd(..) => Opcode::Snd,
+            Instruction::Rcv(..) => Opcode::Rcv,
+            Instruction::Set(..) => Opcode::Set,
+            Instruction::Add(..) => Opcode::Add,
+            Instruction::Sub(..) => Opcode::Sub,
+            Instruction::Mul(..) => Opcode::Mul,
+            Instruction::Mod(..) => Opcode::Mod,
+            Instruction::Jgz(..) => Opcode::Jgz,
+            Instruction::Jnz(..) => Opcode::Jnz,
+            Instruction::Native(..) => Opcode::Native,
+        }
+    }
+
+    /// Operands of the instruction.
+    pub fn operands(&self) -> Vec<Val> {
+        match *self {
+            Instruction::Snd(a) | Instruction::Rcv(a) => vec![a],
+            Instruction::Set(a, b) |
+            Instruction::Add(a, b) |
+            Instruction::Sub(a, b) |
+            Instruction::Mul(a, b) |
+            Instruction::Mod(a, b) |
+            Instruction::Jgz(a, b) |
+            Instruction::Jnz(a, b) => vec![a, b],
+            Instruction::Native(..) => vec![],
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Instruction::Snd(a) => write!(f, "snd {}", a),
+            Instruction::Rcv(a) => write!(f, "rcv {}", a),
+            Instruction::Set(a, b) => write!(f, "set {} {}", a, b),
+            Instruction::Add(a, b) => write!(f, "add {} {}", a, b),
+            Instruction::Sub(a, b) => write!(f, "sub {} {}", a, b),
+            Instruction::Mul(a, b) => write!(f, "mul {} {}", a, b),
+            Instruction::Mod(a, b) => write!(f, "mod {} {}", a, b),
+            Instruction::Jgz(a, b) => write!(f, "jgz {} {}", a, b),
+            Instruction::Jnz(a, b) => write!(f, "jnz {} {}", a, b),
+            Instruction::Native(ref native) => write!(f, "{}", native),
+        }
+    }
+}
+
+/// Parse the lines of a program.
+pub fn parse(content: &str) -> Result<Program> {
+    parse_lines(content, |line| Instruction::new(line))
+}
+
+/// Error of a process stopped by a fault at the given position.
+pub fn fault(pc: usize) -> Error {
+    Error::NoSolution(format!("modulo by zero or overflowing at line {}", pc + 1))
+}
+
+/// Outcome of a `rcv` instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Receive {
+    /// Store the value in the register of the instruction.
+    Value(isize),
+    /// Go on with the next instruction.
+    Skip,
+    /// Wait for a value, executing the instruction again on the next step.
+    Block,
+    /// Stop the process.
+    Stop,
+}
+
+/// Semantics of the `snd` and `rcv` instructions.
+pub trait Io {
+    /// Handle `snd` with the value of its operand.
+    fn send(&mut self, value: isize);
+
+    /// Handle `rcv` with the current value of its operand.
+    fn receive(&mut self, current: isize) -> Receive;
+}
+
+/// Sounds played by `snd`, the last one being recovered by `rcv` when its
+/// operand is not zero, which stops the process.
+#[derive(Debug, Clone, Default)]
+pub struct Sound {
+    pub last: Option<isize>,
+}
+
+impl Io for Sound {
+    fn send(&mut self, value: isize) {
+        self.last = Some(value);
+    }
+
+    fn receive(&mut self, current: isize) -> Receive {
+        if current != 0 { Receive::Stop } else { Receive::Skip }
+    }
+}
+
+/// Messages sent by `snd` to the outbox, and received by `rcv` from the
+/// inbox, waiting for one if it is empty.
+///
+/// Moving the messages from the outbox of a process to the inbox of another
+/// is left to the scheduler.
+#[derive(Debug, Clone, Default)]
+pub struct Queue {
+    pub inbox: VecDeque<isize>,
+    pub outbox: VecDeque<isize>,
+    /// Number of messages sent.
+    pub sent: usize,
+    /// Number of messages received.
+    pub received: usize,
+}
+
+impl Io for Queue {
+    fn send(&mut self, value: isize) {
+        self.outbox.push_back(value);
+        self.sent += 1;
+    }
+
+    fn receive(&mut self, _current: isize) -> Receive {
+        match self.inbox.pop_front() {
+            Some(value) => {
+                self.received += 1;
+                Receive::Value(value)
+            },
+            None => Receive::Block,
+        }
+    }
+}
+
+/// Registers of a process, addressed by their names.
+pub trait Registers {
+    /// Value of a register, zero if never set.
+    fn get(&self, reg: char) -> isize;
+
+    fn set(&mut self, reg: char, value: isize);
+
+    /// Value of an operand.
+    fn value(&self, val: Val) -> isize {
+        match val {
+            Val::Int(v) => v,
+            Val::Reg(reg) => self.get(reg),
+        }
+    }
+}
+
+/// State of a process after a step.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum State {
+    /// The instruction has been executed.
+    Running,
+    /// The process waits for a message.
+    Blocked,
+    /// The process stopped, or jumped out of the program.
+    Halted,
+    /// The instruction is a modulo by zero or overflowing, and the process
+    /// stopped on it.
+    Fault,
+}
+
+/// Process executing a program.
+#[derive(Debug, Clone)]
+pub struct Proc<I: Io> {
+    pub pc: usize,
+    pub regs: HashMap<char, isize>,
+    pub io: I,
+    /// Number of executed instructions of each opcode.
+    counts: [usize; NB_OPCODES],
+    halted: bool,
+}
+
+impl<I: Io> Proc<I> {
+    pub fn new(io: I) -> Proc<I> {
+        Proc {
+            pc: 0,
+            regs: HashMap::new(),
+            io,
+            counts: [0; NB_OPCODES],
+            halted: false,
+        }
+    }
+
+    /// Value of a register, zero if never set.
+    pub fn register(&self, reg: char) -> isize {
+        *self.regs.get(&reg).unwrap_or(&0)
+    }
+
+    /// Whether the process halted.
+    pub fn halted(&self) -> bool {
+        self.halted
+    }
+
+    /// Number of executed instructions with the given opcode.
+    pub fn count(&self, opcode: Opcode) -> usize {
+        self.counts[opcode as usize]
+    }
+
+    fn value(&self, val: Val) -> isize {
+        match val {
+            Val::Int(v) => v,
+            Val::Reg(reg) => self.register(reg),
+        }
+    }
+
+    /// Store the result of the operation in the register `a`, ignoring
+    /// instructions whose first operand is not a register.
+    fn apply<F: Fn(isize, isize) -> isize>(&mut self, a: Val, b: Val, op: F) {
+        if let Val::Reg(reg) = a {
+            let val = op(self.value(a), self.value(b));
+            self.regs.insert(reg, val);
+        }
+    }
+
+    /// Jump by the given offset from the current instruction.
+    fn jump(&mut self, offset: isize, len: usize) -> State {
+        let target = self.pc as isize + offset;
+        if target < 0 || target >= len as isize {
+            self.halted = true;
+            return State::Halted;
+        }
+        self.pc = target as usize;
+        State::Running
+    }
+
+    /// Execute the current instruction of the program.
+    pub fn step(&mut self, program: &Program) -> State {
+        if self.halted || self.pc >= program.len() {
+            self.halted = true;
+            return State::Halted;
+        }
+        self.execute(&program[self.pc], program.len())
+    }
+
+    /// Execute the instruction at the current position of a program of the
+    /// given length.
+    fn execute(&mut self, instruction: &Instruction, len: usize) -> State {
+        let state = match *instruction {
+            Instruction::Snd(a) => {
+                let val = self.value(a);
+                self.io.send(val);
+                State::Running
+            },
+            Instruction::Rcv(a) => {
+                let current = self.value(a);
+                match self.io.receive(current) {
+                    Receive::Value(val) => {
+                        if let Val::Reg(reg) = a {
+                            self.regs.insert(reg, val);
+                        }
+                        State::Running
+                    },
+                    Receive::Skip => State::Running,
+                    Receive::Block => return State::Blocked,
+                    Receive::Stop => {
+                        self.halted = true;
+                        State::Halted
+                    },
+                }
+            },
+            Instruction::Set(a, b) => {
+                self.apply(a, b, |_, b| b);
+                State::Running
+            },
+            Instruction::Add(a, b) => {
+                self.apply(a, b, |a, b| a + b);
+                State::Running
+            },
+            Instruction::Sub(a, b) => {
+                self.apply(a, b, |a, b| a - b);
+                State::Running
+            },
+            Instruction::Mul(a, b) => {
+                self.apply(a, b, |a, b| a * b);
+                State::Running
+            },
+            Instruction::Mod(Val::Reg(reg), b) => match self.register(reg).checked_rem(self.value(b)) {
+                Some(value) => {
+                    self.regs.insert(reg, value);
+                    State::Running
+                },
+                None => {
+                    self.halted = true;
+                    return State::Fault;
+                },
+            },
+            Instruction::Mod(..) => State::Running,
+            Instruction::Jgz(a, b) if self.value(a) > 0 => {
+                let offset = self.value(b);
+                self.counts[Opcode::Jgz as usize] += 1;
+                return self.jump(offset, len);
+            },
+            Instruction::Jnz(a, b) if self.value(a) != 0 => {
+                let offset = self.value(b);
+                self.counts[Opcode::Jnz as usize] += 1;
+                return self.jump(offset, len);
+            },
+            Instruction::Jgz(..) | Instruction::Jnz(..) => State::Running,
+            Instruction::Native(ref native) => {
+                // Fall back to the replaced instructions when the native
+                // operation doesn't apply to the current registers
+                if !native.apply(self) {
+                    return self.execute(&native.head(), len);
+                }
+                self.counts[Opcode::Native as usize] += 1;
+                return self.jump(native.size() as isize, len);
+            },
+        };
+        self.counts[instruction.opcode() as usize] += 1;
+        self.pc += 1;
+        state
+    }
+
+    /// Run the program until the process halts or blocks.
+    pub fn run(&mut self, program: &Program) -> State {
+        loop {
+            match self.step(program) {
+                State::Running => (),
+                state => return state,
+            }
+        }
+    }
+}
+
+impl<I: Io> Registers for Proc<I> {
+    fn get(&self, reg: char) -> isize {
+        self.register(reg)
+    }
+
+    fn set(&mut self, reg: char, value: isize) {
+        self.regs.insert(reg, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{Opcode, Proc, Queue, Sound, State, parse};
+
+    #[test]
+    fn sound_mode() {
+        let program = parse("snd 3\nadd a 1\nrcv a\nsnd 4").unwrap();
+        let mut vproc = Proc::new(Sound::default());
+        assert_eq!(vproc.run(&program), State::Halted);
+        assert_eq!((vproc.io.last, vproc.pc), (Some(3), 3));
+    }
+
+    #[test]
+    fn queue_mode() {
+        let program = parse("snd a\nrcv b\nmul b 2\njnz b -3").unwrap();
+        let mut vproc = Proc::new(Queue::default());
+        vproc.regs.insert('a', 7);
+        assert_eq!(vproc.run(&program), State::Blocked);
+        vproc.io.inbox.push_back(5);
+        assert_eq!(vproc.run(&program), State::Blocked);
+        assert_eq!((vproc.register('b'), vproc.io.sent, vproc.count(Opcode::Mul)), (10, 2, 1));
+    }
+
+    #[test]
+    fn modulo_by_zero() {
+        let program = parse("set a 5\nmod a b\nsnd a").unwrap();
+        let mut vproc = Proc::new(Sound::default());
+        assert_eq!(vproc.run(&program), State::Fault);
+        assert_eq!((vproc.pc, vproc.register('a'), vproc.step(&program)), (1, 5, State::Halted));
+    }
+
+}
@@ -0,0 +1,343 @@
+//! Control-flow analysis of the programs, and replacement of known loops by
+//! native operations.
+//!
+//! A recognised loop keeps its instructions, only the first one is replaced
+//! by a `Native` operation computing the effect of the whole loop and jumping
+//! past it. When the registers don't satisfy the preconditions of the native
+//! operation, the replaced instruction is executed instead, so the program
+//! behaves as the original one in every case.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use super::{Instruction, Program, Registers, Val, parse};
+
+/// Sequence of instructions without jumps, except for the last one, and
+/// without jump targets, except for the first one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    /// Position of the first instruction.
+    pub start: usize,
+    /// Position following the last instruction.
+    pub end: usize,
+    /// Positions of the blocks executed next, the length of the program
+    /// standing for its end.
+    pub successors: Vec<usize>,
+}
+
+/// Control-flow graph of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cfg {
+    pub blocks: Vec<Block>,
+    /// Whether some jump offsets are registers, so that the targets of the
+    /// jumps are unknown.
+    pub dynamic: bool,
+}
+
+/// Target of the jump at the given position, `None` if the instruction is not
+/// a jump or its offset is not a constant.
+pub fn jump_target(program: &Program, pc: usize) -> Option<isize> {
+    match program[pc] {
+        Instruction::Jgz(_, Val::Int(offset)) |
+        Instruction::Jnz(_, Val::Int(offset)) => Some(pc as isize + offset),
+        _ => None,
+    }
+}
+
+/// Whether the jump at the given position is always or never taken.
+pub(crate) fn constant_jump(instruction: &Instruction) -> Option<bool> {
+    match *instruction {
+        Instruction::Jgz(Val::Int(cond), _) => Some(cond > 0),
+        Instruction::Jnz(Val::Int(cond), _) => Some(cond != 0),
+        _ => None,
+    }
+}
+
+impl Cfg {
+    pub fn new(program: &Program) -> Cfg {
+        let len = program.len();
+        let mut dynamic = false;
+        let mut leaders: BTreeSet<usize> = BTreeSet::new();
+        leaders.insert(0);
+        for (pc, instruction) in program.iter().enumerate() {
+            match *instruction {
+                Instruction::Jgz(..) | Instruction::Jnz(..) => {
+                    match jump_target(program, pc) {
+                        Some(target) if target >= 0 && (target as usize) < len => {
+                            leaders.insert(target as usize);
+                        },
+                        Some(_) => (),
+                        None => dynamic = true,
+                    }
+                    leaders.insert(pc + 1);
+                },
+                _ => (),
+            }
+        }
+        let starts: Vec<usize> = leaders.into_iter().filter(|&pc| pc < len).collect();
+        let blocks = starts.iter()
+                           .enumerate()
+                           .map(|(i, &start)| {
+                               let end = starts.get(i + 1).cloned().unwrap_or(len);
+                               Block { start, end, successors: successors(program, end - 1) }
+                           })
+                           .collect();
+        Cfg { blocks, dynamic }
+    }
+
+    /// Index of the block containing the instruction at the given position.
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        self.blocks.iter().position(|block| block.start <= pc && pc < block.end)
+    }
+
+    /// Loops of the program, as the positions of their first and last
+    /// instructions, the last one jumping back to the first one.
+    pub fn loops(&self) -> Vec<(usize, usize)> {
+        let mut loops: Vec<(usize, usize)> = Vec::new();
+        for block in &self.blocks {
+            for &successor in &block.successors {
+                if successor <= block.start {
+                    loops.push((successor, block.end - 1));
+                }
+            }
+        }
+        loops.sort();
+        loops
+    }
+
+    /// Whether the instructions of the range can only be entered through the
+    /// first one.
+    pub fn single_entry(&self, start: usize, end: usize) -> bool {
+        !self.dynamic && self.blocks.iter().all(|block| {
+            let last = block.end - 1;
+            (start <= last && last < end) || block.successors.iter().all(|&s| s <= start || s >= end)
+        })
+    }
+}
+
+/// Positions of the instructions that can follow the one at the given position.
+pub(crate) fn successors(program: &Program, pc: usize) -> Vec<usize> {
+    let len = program.len() as isize;
+    let clamp = |target: isize| if target < 0 || target > len { len as usize } else { target as usize };
+    match (jump_target(program, pc), constant_jump(&program[pc])) {
+        (Some(target), Some(true)) => vec![clamp(target)],
+        (Some(_), Some(false)) | (None, _) => vec![pc + 1],
+        (Some(target), None) => {
+            let mut next = vec![pc + 1, clamp(target)];
+            next.dedup();
+            next
+        },
+    }
+}
+
+/// Native operations replacing known loops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Native {
+    /// `dst += src * count` then `count = 0`, replacing a loop adding `src`
+    /// to `dst` while decrementing `count` down to zero.
+    MulAdd { dst: char, src: Val, count: char },
+    /// `flag = 0` if `n` is composite, replacing two nested loops trying all
+    /// the products `d * e` for `d` and `e` in `[2, n)`, using `tmp` to
+    /// compare them to `n`.
+    Composite { n: Val, flag: char, d: char, e: char, tmp: char },
+}
+
+/// Loop adding `Y` to `X`, `C` times.
+const MUL_ADD: [&str; 2] = ["add X Y\nsub C 1\njnz C -2", "add X Y\nadd C -1\njnz C -2"];
+
+/// Nested loops clearing `F` if `N` is the product of two numbers `D` and `E`.
+const COMPOSITE: &str = "set D 2\nset E 2\nset G D\nmul G E\nsub G N\njnz G 2\nset F 0\nsub E -1\n\
+                         set G E\nsub G N\njnz G -8\nsub D -1\nset G D\nsub G N\njnz G -13";
+
+impl Native {
+    /// Number of instructions replaced by the operation.
+    pub fn size(&self) -> usize {
+        match *self {
+            Native::MulAdd { .. } => 3,
+            Native::Composite { .. } => 15,
+        }
+    }
+
+    /// First replaced instruction.
+    pub fn head(&self) -> Instruction {
+        match *self {
+            Native::MulAdd { dst, src, .. } => Instruction::Add(Val::Reg(dst), src),
+            Native::Composite { d, .. } => Instruction::Set(Val::Reg(d), Val::Int(2)),
+        }
+    }
+
+    /// Apply the operation to the registers, returning `false` without
+    /// changing them if the replaced loops wouldn't terminate.
+    pub fn apply<R: Registers>(&self, regs: &mut R) -> bool {
+        match *self {
+            Native::MulAdd { dst, src, count } => {
+                let times = regs.get(count);
+                if times <= 0 {
+                    return false;
+                }
+                let value = regs.get(dst) + regs.value(src) * times;
+                regs.set(dst, value);
+                regs.set(count, 0);
+                true
+            },
+            Native::Composite { n, flag, d, e, tmp } => {
+                let n = regs.value(n);
+                if n < 3 {
+                    return false;
+                }
+                if (2..).take_while(|k| k * k <= n).any(|k| n % k == 0) {
+                    regs.set(flag, 0);
+                }
+                regs.set(d, n);
+                regs.set(e, n);
+                regs.set(tmp, 0);
+                true
+            },
+        }
+    }
+
+    /// Recognise a known loop at the start of the instructions.
+    pub fn recognise(code: &[Instruction]) -> Option<Native> {
+        if let Some(vars) = matches(COMPOSITE, code) {
+            let regs = registers(&vars, &['D', 'E', 'F', 'G'])?;
+            if distinct(&regs) && !regs.iter().any(|&r| vars[&'N'] == Val::Reg(r)) {
+                return Some(Native::Composite { n: vars[&'N'], flag: regs[2], d: regs[0], e: regs[1], tmp: regs[3] });
+            }
+        }
+        for template in &MUL_ADD {
+            if let Some(vars) = matches(template, code) {
+                let regs = registers(&vars, &['X', 'C'])?;
+                if distinct(&regs) && !regs.iter().any(|&r| vars[&'Y'] == Val::Reg(r)) {
+                    return Some(Native::MulAdd { dst: regs[0], src: vars[&'Y'], count: regs[1] });
+                }
+            }
+        }
+        None
+    }
+}
+
+impl fmt::Display for Native {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Native::MulAdd { dst, src, count } => write!(f, "native {} += {} * {}", dst, src, count),
+            Native::Composite { n, flag, .. } => write!(f, "native {} = 0 if composite({})", flag, n),
+        }
+    }
+}
+
+/// Match the instructions against a template whose uppercase registers stand
+/// for any operand, returning the operand of each of them.
+fn matches(template: &str, code: &[Instruction]) -> Option<HashMap<char, Val>> {
+    let template = parse(template).unwrap();
+    if code.len() < template.len() {
+        return None;
+    }
+    let mut vars: HashMap<char, Val> = HashMap::new();
+    for (expected, instruction) in template.iter().zip(code) {
+        if expected.opcode() != instruction.opcode() {
+            return None;
+        }
+        for (pattern, operand) in expected.operands().into_iter().zip(instruction.operands()) {
+            match pattern {
+                Val::Reg(var) if var.is_uppercase() => {
+                    if *vars.entry(var).or_insert(operand) != operand {
+                        return None;
+                    }
+                },
+                _ if pattern != operand => return None,
+                _ => (),
+            }
+        }
+    }
+    Some(vars)
+}
+
+/// Registers bound to the variables, `None` if some of them are not registers.
+fn registers(vars: &HashMap<char, Val>, names: &[char]) -> Option<Vec<char>> {
+    names.iter()
+         .map(|name| match vars.get(name) {
+             Some(&Val::Reg(reg)) => Some(reg),
+             _ => None,
+         })
+         .collect()
+}
+
+fn distinct(regs: &[char]) -> bool {
+    regs.iter().enumerate().all(|(i, reg)| !regs[i + 1..].contains(reg))
+}
+
+/// Replace the known loops of the program by native operations.
+///
+/// Programs whose jump targets are not all known are returned unchanged.
+pub fn optimize(program: &Program) -> Program {
+    let cfg = Cfg::new(program);
+    let mut optimized = program.clone();
+    if cfg.dynamic {
+        return optimized;
+    }
+    let mut pc = 0;
+    while pc < program.len() {
+        match Native::recognise(&program[pc..]) {
+            Some(native) if cfg.single_entry(pc, pc + native.size()) => {
+                optimized[pc] = Instruction::Native(native);
+                pc += native.size();
+            },
+            _ => pc += 1,
+        }
+    }
+    optimized
+}
+
+#[cfg(test)]
+mod tests {
+
+    use vm::{Instruction, Opcode, Proc, Sound, Val, parse};
+    use super::{COMPOSITE, Cfg, Native, optimize};
+
+    const PROGRAM: &str = "set b 3\n\
+                           set c 4\n\
+                           add a b\n\
+                           sub c 1\n\
+                           jnz c -2\n\
+                           jnz a 2\n\
+                           set a 0";
+
+    #[test]
+    fn control_flow_graph() {
+        let cfg = Cfg::new(&parse(PROGRAM).unwrap());
+        let blocks: Vec<(usize, usize, Vec<usize>)> = cfg.blocks
+                                                         .into_iter()
+                                                         .map(|b| (b.start, b.end, b.successors))
+                                                         .collect();
+        assert_eq!(blocks, vec![(0, 2, vec![2]), (2, 5, vec![5, 2]), (5, 6, vec![6, 7]), (6, 7, vec![7])]);
+        assert_eq!(Cfg::new(&parse(PROGRAM).unwrap()).loops(), vec![(2, 4)]);
+    }
+
+    #[test]
+    fn multiplication_loop() {
+        let program = optimize(&parse(PROGRAM).unwrap());
+        assert_eq!(program[2], Instruction::Native(Native::MulAdd { dst: 'a', src: Val::Reg('b'), count: 'c' }));
+        let mut vproc = Proc::new(Sound::default());
+        vproc.run(&program);
+        assert_eq!((vproc.register('a'), vproc.register('c'), vproc.count(Opcode::Add)), (12, 0, 0));
+    }
+
+    #[test]
+    fn composite_loops() {
+        let template = COMPOSITE.replace('D', "d")
+                                .replace('E', "e")
+                                .replace('F', "f")
+                                .replace('G', "g")
+                                .replace('N', "b");
+        for n in 3..40 {
+            let program = parse(&format!("set b {}\nset f 1\n{}", n, template)).unwrap();
+            let mut original = Proc::new(Sound::default());
+            let mut native = Proc::new(Sound::default());
+            original.run(&program);
+            native.run(&optimize(&program));
+            assert_eq!(original.regs, native.regs);
+            assert_eq!(native.count(Opcode::Native), 1);
+        }
+    }
+
+}
@@ -0,0 +1,192 @@
+//! Scheduling of several processes running the same program and exchanging
+//! messages through channels.
+
+use super::{Queue, Registers, State};
+use super::compile::{Code, Machine};
+
+/// Maximum number of instructions executed by a process before the next one
+/// is scheduled, so that a process never blocking doesn't starve the others.
+const QUANTUM: usize = 10_000;
+
+/// Connections between the processes, telling where the messages sent by
+/// each of them are delivered.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Channels {
+    /// Each process sends to the next one, the last one to the first one.
+    Ring,
+    /// Processes `2k` and `2k + 1` send to each other, the last one sending
+    /// to itself if the number of processes is odd.
+    Pairwise,
+    /// Each process sends to all the other ones.
+    Broadcast,
+}
+
+impl Channels {
+    /// Processes receiving the messages sent by the given one.
+    pub fn destinations(&self, from: usize, count: usize) -> Vec<usize> {
+        match *self {
+            Channels::Ring => vec![(from + 1) % count],
+            Channels::Pairwise => vec![if from ^ 1 < count { from ^ 1 } else { from }],
+            Channels::Broadcast => (0..count).filter(|&to| to != from).collect(),
+        }
+    }
+}
+
+/// Reason for the processes to stop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    /// All the processes halted.
+    Halted,
+    /// The given processes wait for messages that will never come, the other
+    /// ones being halted.
+    Deadlock(Vec<usize>),
+    /// The given processes stopped on a fault, at the given positions.
+    Fault(Vec<(usize, usize)>),
+}
+
+/// Messages exchanged by a process.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub sent: usize,
+    pub received: usize,
+    /// Messages delivered but not received yet.
+    pub pending: usize,
+}
+
+/// Scheduler running processes in turn, each until it blocks, halts or
+/// exhausts its quantum, and delivering their messages between the turns.
+pub struct Scheduler<'a> {
+    processes: Vec<Machine<'a, Queue>>,
+    channels: Channels,
+    /// Processes stopped on a fault, with the positions of the faults.
+    faults: Vec<(usize, usize)>,
+}
+
+impl<'a> Scheduler<'a> {
+    /// Create the given number of processes, with their register `p` set to
+    /// their identifier.
+    pub fn new(code: &'a Code, count: usize, channels: Channels) -> Scheduler<'a> {
+        let processes = (0..count).map(|id| {
+                                      let mut process = Machine::new(code, Queue::default());
+                                      process.set('p', id as isize);
+                                      process
+                                  })
+                                  .collect();
+        Scheduler { processes, channels, faults: Vec::new() }
+    }
+
+    pub fn process(&self, id: usize) -> &Machine<'a, Queue> {
+        &self.processes[id]
+    }
+
+    /// Messages exchanged by each process.
+    pub fn stats(&self) -> Vec<Stats> {
+        self.processes
+            .iter()
+            .map(|process| Stats {
+                sent: process.io.sent,
+                received: process.io.received,
+                pending: process.io.inbox.len(),
+            })
+            .collect()
+    }
+
+    /// Run a turn of a process, returning whether it executed instructions.
+    fn turn(&mut self, id: usize) -> bool {
+        let mut executed = false;
+        for _ in 0..QUANTUM {
+            let (halted, state, pc) = {
+                let process = &mut self.processes[id];
+                let halted = process.halted();
+                let state = process.step();
+                (halted, state, process.pc)
+            };
+            match state {
+                State::Running => executed = true,
+                State::Halted => return executed || !halted,
+                State::Blocked => return executed,
+                State::Fault => {
+                    self.faults.push((id, pc));
+                    return true;
+                },
+            }
+        }
+        executed
+    }
+
+    /// Move the messages sent by the process to the inboxes of the processes
+    /// connected to it, returning whether there were some.
+    fn deliver(&mut self, from: usize) -> bool {
+        let messages: Vec<isize> = self.processes[from].io.outbox.drain(..).collect();
+        for to in self.channels.destinations(from, self.processes.len()) {
+            self.processes[to].io.inbox.extend(&messages);
+        }
+        !messages.is_empty()
+    }
+
+    /// Run the processes until all of them halted or are blocked, the faults
+    /// being reported first.
+    pub fn run(&mut self) -> Outcome {
+        loop {
+            let mut progress = false;
+            for id in 0..self.processes.len() {
+                progress |= self.turn(id);
+                progress |= self.deliver(id);
+            }
+            if !progress {
+                break;
+            }
+        }
+        if !self.faults.is_empty() {
+            return Outcome::Fault(self.faults.clone());
+        }
+        let blocked: Vec<usize> = (0..self.processes.len()).filter(|&id| !self.processes[id].halted()).collect();
+        if blocked.is_empty() { Outcome::Halted } else { Outcome::Deadlock(blocked) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use vm::{Registers, parse};
+    use vm::compile::compile;
+    use super::{Channels, Outcome, Scheduler, Stats};
+
+    /// Send the identifier, receive a message, send it increased, and stop
+    /// at the third message.
+    const PROGRAM: &str = "snd p\nrcv a\nadd a 10\nsnd a\nrcv b\nrcv c";
+
+    #[test]
+    fn ring_deadlock() {
+        let code = compile(&parse(PROGRAM).unwrap());
+        let mut scheduler = Scheduler::new(&code, 3, Channels::Ring);
+        assert_eq!(scheduler.run(), Outcome::Deadlock(vec![0, 1, 2]));
+        let received: Vec<(isize, isize)> = (0..3).map(|id| scheduler.process(id))
+                                                  .map(|process| (process.get('a'), process.get('b')))
+                                                  .collect();
+        assert_eq!(received, vec![(12, 11), (10, 12), (11, 10)]);
+        assert_eq!(scheduler.stats()[0], Stats { sent: 2, received: 2, pending: 0 });
+    }
+
+    #[test]
+    fn broadcast_messages() {
+        let code = compile(&parse(PROGRAM).unwrap());
+        let mut scheduler = Scheduler::new(&code, 3, Channels::Broadcast);
+        assert_eq!(scheduler.run(), Outcome::Halted);
+        let stats = scheduler.stats();
+        assert_eq!(stats.iter().map(|s| (s.sent, s.received, s.pending)).collect::<Vec<_>>(),
+                   vec![(2, 3, 1), (2, 3, 1), (2, 3, 1)]);
+        let code = compile(&parse("snd p\nrcv a").unwrap());
+        let mut scheduler = Scheduler::new(&code, 3, Channels::Pairwise);
+        assert_eq!(scheduler.run(), Outcome::Halted);
+        assert_eq!((scheduler.process(1).get('a'), scheduler.process(2).get('a')), (0, 2));
+    }
+
+    #[test]
+    fn faulty_process() {
+        let code = compile(&parse("snd 1\nrcv a\nmod a p").unwrap());
+        let mut scheduler = Scheduler::new(&code, 2, Channels::Pairwise);
+        assert_eq!(scheduler.run(), Outcome::Fault(vec![(0, 2)]));
+    }
+
+}
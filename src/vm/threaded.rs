@@ -0,0 +1,178 @@
+//! Execution of the message-passing processes on their own threads, the
+//! messages being exchanged through channels.
+//!
+//! The processes share a deadlock detector counting the messages sent to
+//! each of them and not received yet. A process about to wait for a message
+//! first checks whether all the other ones are halted or waiting without
+//! pending messages, in which case none of them can ever go on and all of
+//! them are stopped.
+
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{Receiver, Sender, channel};
+use std::thread;
+
+use super::{Io, Receive, Registers, State};
+use super::compile::{Code, Machine};
+use super::schedule::{Channels, Outcome, Stats};
+
+/// State of the processes, as seen by the deadlock detector.
+#[derive(Debug, Clone, Default)]
+struct Processes {
+    /// Messages sent to each process and not received yet.
+    pending: Vec<usize>,
+    waiting: Vec<bool>,
+    halted: Vec<bool>,
+    deadlock: bool,
+}
+
+impl Processes {
+    /// Whether none of the processes can go on.
+    fn stuck(&self) -> bool {
+        (0..self.pending.len()).all(|id| self.halted[id] || (self.waiting[id] && self.pending[id] == 0))
+    }
+}
+
+/// Result of a thread: whether it was stopped by a deadlock, the position of
+/// its fault if any, and the numbers of messages sent and received.
+type Report = (bool, Option<usize>, usize, usize);
+
+/// Deadlock detector shared by the threads.
+#[derive(Debug, Default)]
+struct Detector {
+    processes: Mutex<Processes>,
+    /// Signaled when a message is sent or a process halts.
+    changed: Condvar,
+}
+
+/// Messages sent and received through channels.
+struct Channel {
+    id: usize,
+    outputs: Vec<(usize, Sender<isize>)>,
+    input: Receiver<isize>,
+    detector: Arc<Detector>,
+    sent: usize,
+    received: usize,
+    /// Whether the process has been stopped by a deadlock.
+    deadlocked: bool,
+}
+
+impl Io for Channel {
+    fn send(&mut self, value: isize) {
+        let mut processes = self.detector.processes.lock().unwrap();
+        for &(to, ref output) in &self.outputs {
+            processes.pending[to] += 1;
+            // The receiver may have halted already
+            let _ = output.send(value);
+        }
+        self.sent += 1;
+        self.detector.changed.notify_all();
+    }
+
+    fn receive(&mut self, _current: isize) -> Receive {
+        let mut processes = self.detector.processes.lock().unwrap();
+        while processes.pending[self.id] == 0 {
+            processes.waiting[self.id] = true;
+            if !processes.deadlock && processes.stuck() {
+                processes.deadlock = true;
+                self.detector.changed.notify_all();
+            }
+            if processes.deadlock {
+                self.deadlocked = true;
+                return Receive::Stop;
+            }
+            processes = self.detector.changed.wait(processes).unwrap();
+        }
+        processes.waiting[self.id] = false;
+        processes.pending[self.id] -= 1;
+        drop(processes);
+        self.received += 1;
+        Receive::Value(self.input.recv().unwrap())
+    }
+}
+
+/// Run the given number of processes on their own threads, with their
+/// register `p` set to their identifier, until all of them halted or are
+/// deadlocked.
+///
+/// Return the same outcome and statistics as `Scheduler::run` and
+/// `Scheduler::stats`.
+pub fn run(code: &Code, count: usize, channels: Channels) -> (Outcome, Vec<Stats>) {
+    let code = Arc::new(code.clone());
+    let detector = Arc::new(Detector {
+        processes: Mutex::new(Processes {
+            pending: vec![0; count],
+            waiting: vec![false; count],
+            halted: vec![false; count],
+            deadlock: false,
+        }),
+        changed: Condvar::new(),
+    });
+    let (senders, receivers): (Vec<Sender<isize>>, Vec<Receiver<isize>>) = (0..count).map(|_| channel()).unzip();
+    let handles: Vec<thread::JoinHandle<Report>> = receivers.into_iter()
+        .enumerate()
+        .map(|(id, input)| {
+            let io = Channel {
+                id,
+                outputs: channels.destinations(id, count)
+                                 .into_iter()
+                                 .map(|to| (to, senders[to].clone()))
+                                 .collect(),
+                input,
+                detector: Arc::clone(&detector),
+                sent: 0,
+                received: 0,
+                deadlocked: false,
+            };
+            let code = Arc::clone(&code);
+            thread::spawn(move || {
+                let mut process = Machine::new(&code, io);
+                process.set('p', id as isize);
+                let fault = if process.run() == State::Fault { Some(process.pc) } else { None };
+                let io = process.io;
+                let mut processes = io.detector.processes.lock().unwrap();
+                processes.halted[id] = true;
+                io.detector.changed.notify_all();
+                (io.deadlocked, fault, io.sent, io.received)
+            })
+        })
+        .collect();
+    let results: Vec<Report> = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    let processes = detector.processes.lock().unwrap();
+    let stats = results.iter()
+                       .enumerate()
+                       .map(|(id, &(_, _, sent, received))| Stats { sent, received, pending: processes.pending[id] })
+                       .collect();
+    let faults: Vec<(usize, usize)> = results.iter()
+                                             .enumerate()
+                                             .filter_map(|(id, result)| result.1.map(|pc| (id, pc)))
+                                             .collect();
+    if !faults.is_empty() {
+        return (Outcome::Fault(faults), stats);
+    }
+    let blocked: Vec<usize> = (0..count).filter(|&id| results[id].0).collect();
+    (if blocked.is_empty() { Outcome::Halted } else { Outcome::Deadlock(blocked) }, stats)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use vm::parse;
+    use vm::compile::compile;
+    use vm::schedule::{Channels, Scheduler};
+    use super::run;
+
+    #[test]
+    fn same_as_scheduler() {
+        let program = parse("snd p\nrcv a\nadd a 10\nsnd a\nrcv b\nrcv c").unwrap();
+        let code = compile(&program);
+        let faulty = compile(&parse("snd 1\nrcv a\nmod a p").unwrap());
+        for &channels in &[Channels::Ring, Channels::Pairwise, Channels::Broadcast] {
+            let mut scheduler = Scheduler::new(&code, 3, channels);
+            let outcome = scheduler.run();
+            assert_eq!(run(&code, 3, channels), (outcome, scheduler.stats()));
+            let mut scheduler = Scheduler::new(&faulty, 3, channels);
+            assert_eq!(run(&faulty, 3, channels).0, scheduler.run());
+        }
+    }
+
+}
@@ -0,0 +1,24 @@
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Path of the binary, built next to the directory of the tests.
+fn binary() -> PathBuf {
+    let mut path = env::current_exe().unwrap();
+    path.pop();
+    if path.ends_with("deps") {
+        path.pop();
+    }
+    path.join(format!("advent-of-code-2017{}", env::consts::EXE_SUFFIX))
+}
+
+#[test]
+fn solvers_only_print_the_report() {
+    let output = Command::new(binary()).args(&["21", "--format", "csv"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0], "day,part,answer,elapsed_ms,input_hash,error");
+    assert!(lines[1].starts_with("21,1,") && lines[2].starts_with("21,2,"));
+}
@@ -0,0 +1,25 @@
+extern crate advent_of_code_2017;
+
+use advent_of_code_2017::prob_08;
+use advent_of_code_2017::solution::{registry, solve};
+
+#[test]
+fn public_parser_and_solvers() {
+    let instructions = prob_08::parse("b inc 5 if a > 1\n\
+                                       a inc 1 if b < 5\n\
+                                       c dec -10 if a >= 1\n\
+                                       c inc -20 if c == 10").unwrap();
+    assert_eq!(prob_08::solve_first_part(&instructions), 1);
+    assert_eq!(prob_08::solve_second_part(&instructions), 10);
+}
+
+#[test]
+fn registered_solver() {
+    let solvers = registry();
+    let solver = solvers.iter().find(|solver| solver.day() == 1).unwrap();
+    let answers: Vec<(u32, String)> = solve(&**solver, "1122").unwrap()
+                                                              .into_iter()
+                                                              .map(|answer| (answer.part, answer.value))
+                                                              .collect();
+    assert_eq!(answers, vec![(1, String::from("3")), (2, String::from("0"))]);
+}